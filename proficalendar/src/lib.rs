@@ -0,0 +1,532 @@
+//! Trading calendars of the exchanges stocks are listed on: which days they
+//! trade, in which time zone and when their session closes. Shared by
+//! profiserve and profisync so that both agree on which bars to expect.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+/// Environment variable naming the calendar file both profiserve and
+/// profisync extend the built-in calendars with.
+pub const CALENDARS_FILE_VAR: &str = "PROFI_CALENDARS_FILE";
+
+/// Calendar file named by [`CALENDARS_FILE_VAR`], if any.
+pub fn calendars_file() -> Option<PathBuf> {
+    std::env::var_os(CALENDARS_FILE_VAR).map(PathBuf::from)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CalendarError {
+    #[error("failed to read calendar file {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("invalid calendar file {0}: {1}")]
+    Parse(String, serde_json::Error),
+    #[error("invalid {field} for {exchange}: {value}")]
+    InvalidField {
+        exchange: String,
+        field: &'static str,
+        value: String,
+    },
+}
+
+/// How a holiday falling on a weekend is made up for.
+#[derive(Debug, Clone, Copy)]
+enum Observance {
+    /// Not made up for.
+    Unobserved,
+    /// Saturday moves to Friday and Sunday to Monday, as in the US.
+    Nearest,
+    /// Sunday moves to Monday, a Saturday holiday is lost.
+    SundayToMonday,
+    /// Sunday moves to the next day that is not a holiday already, as in Japan.
+    SundayToNext,
+    /// Moves to the next weekday that is not a holiday already, as in the UK.
+    NextWeekday,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HolidayRule {
+    /// The same date every year from `since` to `until`, both inclusive.
+    Fixed {
+        month: u32,
+        day: u32,
+        observance: Observance,
+        since: i32,
+        until: i32,
+    },
+    /// The `n`th `weekday` of `month`, counted from the end when negative,
+    /// every year from `since` to `until`, both inclusive.
+    NthWeekday {
+        month: u32,
+        weekday: Weekday,
+        n: i8,
+        since: i32,
+        until: i32,
+    },
+    /// Days relative to Easter Sunday, -2 being Good Friday.
+    Easter(i64),
+    /// Japan's spring or autumn equinox day.
+    Equinox { spring: bool },
+    /// Any weekday between two other holidays from `since` on, such as
+    /// Japan's citizen's holiday.
+    Sandwiched { since: i32 },
+}
+
+const fn fixed(month: u32, day: u32, observance: Observance) -> HolidayRule {
+    HolidayRule::Fixed { month, day, observance, since: i32::MIN, until: i32::MAX }
+}
+
+const fn nth(month: u32, weekday: Weekday, n: i8) -> HolidayRule {
+    HolidayRule::NthWeekday { month, weekday, n, since: i32::MIN, until: i32::MAX }
+}
+
+impl HolidayRule {
+    /// The same rule, only from `year` on.
+    const fn since(self, year: i32) -> Self {
+        match self {
+            HolidayRule::Fixed { month, day, observance, until, .. } => {
+                HolidayRule::Fixed { month, day, observance, since: year, until }
+            }
+            HolidayRule::NthWeekday { month, weekday, n, until, .. } => {
+                HolidayRule::NthWeekday { month, weekday, n, since: year, until }
+            }
+            rule => rule,
+        }
+    }
+
+    /// The same rule, only up to `year`.
+    const fn until(self, year: i32) -> Self {
+        match self {
+            HolidayRule::Fixed { month, day, observance, since, .. } => {
+                HolidayRule::Fixed { month, day, observance, since, until: year }
+            }
+            HolidayRule::NthWeekday { month, weekday, n, since, .. } => {
+                HolidayRule::NthWeekday { month, weekday, n, since, until: year }
+            }
+            rule => rule,
+        }
+    }
+
+    /// Date of the holiday in `year` before any observance, and how it is
+    /// observed when it falls on a weekend.
+    fn date(&self, year: i32) -> Option<(NaiveDate, Observance)> {
+        match *self {
+            HolidayRule::Fixed { month, day, observance, since, until } => {
+                if year < since || year > until {
+                    return None;
+                }
+                Some((NaiveDate::from_ymd_opt(year, month, day)?, observance))
+            }
+            HolidayRule::NthWeekday { since, until, .. } if year < since || year > until => None,
+            HolidayRule::NthWeekday { month, weekday, n, .. } if n > 0 => {
+                Some((NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)?, Observance::Unobserved))
+            }
+            HolidayRule::NthWeekday { month, weekday, n, .. } => {
+                let next_month: NaiveDate = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)?
+                };
+                let last_day: NaiveDate = next_month.pred_opt()?;
+                let back: u64 = u64::from((7 + last_day.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7);
+                let weeks: u64 = u64::from(n.unsigned_abs() - 1) * 7;
+
+                Some((last_day - Days::new(back + weeks), Observance::Unobserved))
+            }
+            HolidayRule::Easter(offset) => {
+                let easter: NaiveDate = easter_sunday(year)?;
+                Some((easter.checked_add_signed(chrono::Duration::days(offset))?, Observance::Unobserved))
+            }
+            HolidayRule::Equinox { spring } => {
+                // Approximation used for Japan's calendar, valid from 1980 to 2099.
+                // Before 1980 the leap-year correction is negative.
+                let years: f64 = f64::from(year - 1980);
+                let base: f64 = if spring { 20.8431 } else { 23.2488 };
+                let day: i64 = (base + 0.242194 * years).floor() as i64 - (years / 4.0).floor() as i64;
+                let month: u32 = if spring { 3 } else { 9 };
+
+                Some((NaiveDate::from_ymd_opt(year, month, u32::try_from(day).ok()?)?, Observance::SundayToNext))
+            }
+            // Depends on the other holidays, see `TradingCalendar::holidays`
+            HolidayRule::Sandwiched { .. } => None,
+        }
+    }
+}
+
+/// Easter Sunday of the Gregorian calendar.
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a: i32 = year % 19;
+    let b: i32 = year / 100;
+    let c: i32 = year % 100;
+    let d: i32 = b / 4;
+    let e: i32 = b % 4;
+    let f: i32 = (b + 8) / 25;
+    let g: i32 = (b - f + 1) / 3;
+    let h: i32 = (19 * a + b - d - g + 15) % 30;
+    let i: i32 = c / 4;
+    let k: i32 = c % 4;
+    let l: i32 = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m: i32 = (a + 11 * h + 22 * l) / 451;
+    let month: i32 = (h + l - 7 * m + 114) / 31;
+    let day: i32 = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// When and where an exchange trades.
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    pub name: String,
+    pub timezone: Tz,
+    /// Local time the session closes at, after which its bar is expected.
+    pub close: NaiveTime,
+    weekend: Vec<Weekday>,
+    rules: Vec<HolidayRule>,
+    /// Closures that follow no rule, such as lunar holidays, from a calendar file.
+    extra_holidays: BTreeSet<NaiveDate>,
+}
+
+impl TradingCalendar {
+    fn new(name: &str, timezone: Tz, close: (u32, u32), rules: Vec<HolidayRule>) -> Self {
+        Self {
+            name: name.to_string(),
+            timezone,
+            close: NaiveTime::from_hms_opt(close.0, close.1, 0).unwrap(),
+            weekend: vec![Weekday::Sat, Weekday::Sun],
+            rules,
+            extra_holidays: BTreeSet::new(),
+        }
+    }
+
+    /// Calendar of exchanges we know nothing about: every weekday in UTC,
+    /// with the day's bar expected once the day is over.
+    pub fn weekdays() -> Self {
+        Self {
+            close: NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+            ..Self::new("weekdays", Tz::UTC, (0, 0), Vec::new())
+        }
+    }
+
+    pub fn is_weekend(&self, date: NaiveDate) -> bool {
+        self.weekend.contains(&date.weekday())
+    }
+
+    /// Observed holidays of `year` that fall on days the exchange would
+    /// otherwise trade.
+    pub fn holidays(&self, year: i32) -> BTreeSet<NaiveDate> {
+        let mut holidays: BTreeSet<NaiveDate> = self.extra_holidays.iter()
+            .copied()
+            .filter(|date: &NaiveDate| date.year() == year)
+            .collect();
+
+        let dates: Vec<(NaiveDate, Observance)> = self.rules.iter()
+            .filter_map(|rule: &HolidayRule| rule.date(year))
+            .collect();
+
+        // Holidays that are not moved go first so that moved ones can skip them
+        holidays.extend(dates.iter()
+            .map(|(date, _)| *date)
+            .filter(|date: &NaiveDate| !self.is_weekend(*date)));
+
+        // Sandwiched days are taken between holidays as dated, before any is moved
+        let sandwiched: bool = self.rules.iter()
+            .any(|rule: &HolidayRule| matches!(rule, HolidayRule::Sandwiched { since } if year >= *since));
+        if sandwiched {
+            let dated: BTreeSet<NaiveDate> = dates.iter().map(|(date, _)| *date).collect();
+            let between: Vec<NaiveDate> = dated.iter()
+                .filter_map(|date: &NaiveDate| date.succ_opt())
+                .filter(|day: &NaiveDate| {
+                    !dated.contains(day)
+                        && !self.is_weekend(*day)
+                        && day.succ_opt().is_some_and(|next: NaiveDate| dated.contains(&next))
+                })
+                .collect();
+            holidays.extend(between);
+        }
+
+        for &(date, observance) in dates.iter().filter(|(date, _)| self.is_weekend(*date)) {
+            let observed: Option<NaiveDate> = match (observance, date.weekday()) {
+                (Observance::Nearest, Weekday::Sat) => date.pred_opt(),
+                (Observance::Nearest | Observance::SundayToMonday, Weekday::Sun) => date.succ_opt(),
+                (Observance::SundayToNext, Weekday::Sun) | (Observance::NextWeekday, _) => {
+                    date.iter_days()
+                        .skip(1)
+                        .find(|day: &NaiveDate| !self.is_weekend(*day) && !holidays.contains(day))
+                }
+                _ => None,
+            };
+            holidays.extend(observed);
+        }
+
+        holidays
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !self.is_weekend(date) && !self.holidays(date.year()).contains(&date)
+    }
+
+    /// Trading days between `from` and `to`, both inclusive.
+    pub fn trading_days(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let mut holidays: HashMap<i32, BTreeSet<NaiveDate>> = HashMap::new();
+
+        from.iter_days()
+            .take_while(|date: &NaiveDate| *date <= to)
+            .filter(|date: &NaiveDate| {
+                !self.is_weekend(*date)
+                    && !holidays.entry(date.year())
+                        .or_insert_with(|| self.holidays(date.year()))
+                        .contains(date)
+            })
+            .collect()
+    }
+
+    /// Date of `at` in the exchange's time zone.
+    pub fn local_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(&self.timezone).date_naive()
+    }
+
+    /// Latest trading day whose session had closed by `now`, the last date a
+    /// bar can be expected for.
+    pub fn latest_session(&self, now: DateTime<Utc>) -> NaiveDate {
+        let local = now.with_timezone(&self.timezone);
+        let mut date: NaiveDate = local.date_naive();
+        if local.time() < self.close {
+            date = date.pred_opt().unwrap_or(date);
+        }
+
+        while !self.is_trading_day(date) {
+            match date.pred_opt() {
+                Some(previous) => date = previous,
+                None => break,
+            }
+        }
+
+        date
+    }
+}
+
+/// Changes a calendar file makes to an exchange, or the definition of an
+/// exchange that is not built in.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CalendarEntry {
+    /// IANA time zone, such as `Asia/Hong_Kong`.
+    timezone: Option<String>,
+    /// Local session close, such as `16:00`.
+    close: Option<String>,
+    /// Days of the week the exchange is closed, such as `["Fri", "Sat"]`.
+    weekend: Option<Vec<String>>,
+    #[serde(default)]
+    holidays: Vec<NaiveDate>,
+    /// Other names the exchange goes by.
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// Trading calendars by exchange name. Exchanges without a calendar of their
+/// own trade on [`TradingCalendar::weekdays`].
+#[derive(Debug, Clone)]
+pub struct Calendars {
+    by_exchange: HashMap<String, Arc<TradingCalendar>>,
+    default: Arc<TradingCalendar>,
+}
+
+impl Default for Calendars {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Calendars {
+    /// Calendars of the major exchanges, with the holidays that follow fixed
+    /// rules. Lunar holidays are left to calendar files.
+    pub fn builtin() -> Self {
+        use Observance::*;
+
+        let us = TradingCalendar::new("US", chrono_tz::America::New_York, (16, 0), vec![
+            fixed(1, 1, SundayToMonday),
+            nth(1, Weekday::Mon, 3),
+            nth(2, Weekday::Mon, 3),
+            HolidayRule::Easter(-2),
+            nth(5, Weekday::Mon, -1),
+            fixed(6, 19, Nearest).since(2022),
+            fixed(7, 4, Nearest),
+            nth(9, Weekday::Mon, 1),
+            nth(11, Weekday::Thu, 4),
+            fixed(12, 25, Nearest),
+        ]);
+
+        let lse = TradingCalendar::new("LSE", chrono_tz::Europe::London, (16, 30), vec![
+            fixed(1, 1, NextWeekday),
+            HolidayRule::Easter(-2),
+            HolidayRule::Easter(1),
+            nth(5, Weekday::Mon, 1),
+            nth(5, Weekday::Mon, -1),
+            nth(8, Weekday::Mon, -1),
+            fixed(12, 25, NextWeekday),
+            fixed(12, 26, NextWeekday),
+        ]);
+
+        let xetra = TradingCalendar::new("XETRA", chrono_tz::Europe::Berlin, (17, 30), vec![
+            fixed(1, 1, Unobserved),
+            HolidayRule::Easter(-2),
+            HolidayRule::Easter(1),
+            fixed(5, 1, Unobserved),
+            fixed(12, 24, Unobserved),
+            fixed(12, 25, Unobserved),
+            fixed(12, 26, Unobserved),
+            fixed(12, 31, Unobserved),
+        ]);
+
+        let tse = TradingCalendar::new("TSE", chrono_tz::Asia::Tokyo, (15, 30), vec![
+            fixed(1, 1, Unobserved),
+            fixed(1, 2, Unobserved),
+            fixed(1, 3, Unobserved),
+            // Coming of Age, Marine, Respect for the Aged and Sports Days
+            // moved to Mondays in 2000 and 2003
+            fixed(1, 15, SundayToNext).until(1999),
+            nth(1, Weekday::Mon, 2).since(2000),
+            fixed(2, 11, SundayToNext).since(1967),
+            fixed(2, 23, SundayToNext).since(2020),
+            HolidayRule::Equinox { spring: true },
+            fixed(4, 29, SundayToNext),
+            fixed(5, 3, SundayToNext),
+            // A citizen's holiday between May 3rd and 5th until it was named in 2007
+            fixed(5, 4, SundayToNext).since(2007),
+            fixed(5, 5, SundayToNext),
+            fixed(7, 20, SundayToNext).since(1996).until(2002),
+            nth(7, Weekday::Mon, 3).since(2003),
+            fixed(8, 11, SundayToNext).since(2016),
+            fixed(9, 15, SundayToNext).until(2002),
+            nth(9, Weekday::Mon, 3).since(2003),
+            HolidayRule::Equinox { spring: false },
+            fixed(10, 10, SundayToNext).since(1966).until(1999),
+            nth(10, Weekday::Mon, 2).since(2000),
+            fixed(11, 3, SundayToNext),
+            fixed(11, 23, SundayToNext),
+            // The Emperor's Birthday of the Heisei era
+            fixed(12, 23, SundayToNext).since(1989).until(2018),
+            fixed(12, 31, Unobserved),
+            HolidayRule::Sandwiched { since: 1986 },
+        ]);
+
+        let hkex = TradingCalendar::new("HKEX", chrono_tz::Asia::Hong_Kong, (16, 0), vec![
+            fixed(1, 1, SundayToMonday),
+            HolidayRule::Easter(-2),
+            HolidayRule::Easter(1),
+            fixed(5, 1, SundayToMonday),
+            fixed(7, 1, SundayToMonday),
+            fixed(10, 1, SundayToMonday),
+            fixed(12, 25, NextWeekday),
+            fixed(12, 26, NextWeekday),
+        ]);
+
+        let asx = TradingCalendar::new("ASX", chrono_tz::Australia::Sydney, (16, 0), vec![
+            fixed(1, 1, NextWeekday),
+            fixed(1, 26, NextWeekday),
+            HolidayRule::Easter(-2),
+            HolidayRule::Easter(1),
+            fixed(4, 25, Unobserved),
+            nth(6, Weekday::Mon, 2),
+            fixed(12, 25, NextWeekday),
+            fixed(12, 26, NextWeekday),
+        ]);
+
+        let crypto = TradingCalendar {
+            weekend: Vec::new(),
+            ..TradingCalendar::weekdays()
+        };
+
+        let mut calendars = Self {
+            by_exchange: HashMap::new(),
+            default: Arc::new(TradingCalendar::weekdays()),
+        };
+        for (calendar, exchanges) in [
+            (us, &["NYSE", "NASDAQ", "AMEX", "NYSEARCA", "NYSEAMERICAN", "ARCA", "BATS"][..]),
+            (lse, &["LSE"][..]),
+            (xetra, &["XETRA", "FRA"][..]),
+            (tse, &["TSE", "JPX"][..]),
+            (hkex, &["HKEX"][..]),
+            (asx, &["ASX"][..]),
+            (crypto, &["CRYPTO", "CCC"][..]),
+        ] {
+            let calendar: Arc<TradingCalendar> = Arc::new(calendar);
+            for exchange in exchanges {
+                calendars.by_exchange.insert(exchange.to_string(), calendar.clone());
+            }
+        }
+
+        calendars
+    }
+
+    /// The built-in calendars, extended by a JSON file that maps exchange
+    /// names to a time zone, session close, weekend, extra holidays and
+    /// aliases. Exchanges that are not built in start from weekdays in UTC.
+    pub fn load(path: &Path) -> Result<Self, CalendarError> {
+        let display: String = path.display().to_string();
+        let content: String = std::fs::read_to_string(path)
+            .map_err(|e| CalendarError::Io(display.clone(), e))?;
+        let entries: HashMap<String, CalendarEntry> = serde_json::from_str(&content)
+            .map_err(|e| CalendarError::Parse(display, e))?;
+
+        let mut calendars: Self = Self::builtin();
+
+        for (exchange, entry) in entries {
+            let exchange: String = exchange.to_uppercase();
+            let invalid = |field: &'static str, value: &str| CalendarError::InvalidField {
+                exchange: exchange.clone(),
+                field,
+                value: value.to_string(),
+            };
+
+            let mut calendar: TradingCalendar = match calendars.by_exchange.get(&exchange) {
+                Some(calendar) => (**calendar).clone(),
+                None => TradingCalendar {
+                    name: exchange.clone(),
+                    ..TradingCalendar::weekdays()
+                },
+            };
+
+            if let Some(timezone) = &entry.timezone {
+                calendar.timezone = timezone.parse::<Tz>().map_err(|_| invalid("timezone", timezone))?;
+            }
+            if let Some(close) = &entry.close {
+                calendar.close = NaiveTime::parse_from_str(close, "%H:%M")
+                    .map_err(|_| invalid("close", close))?;
+            }
+            if let Some(weekend) = &entry.weekend {
+                calendar.weekend = weekend.iter()
+                    .map(|day: &String| day.parse::<Weekday>().map_err(|_| invalid("weekend", day)))
+                    .collect::<Result<Vec<Weekday>, CalendarError>>()?;
+            }
+            calendar.extra_holidays.extend(entry.holidays);
+
+            // Aliases of a built-in exchange keep sharing its calendar
+            let calendar: Arc<TradingCalendar> = Arc::new(calendar);
+            let previous: Option<Arc<TradingCalendar>> = calendars.by_exchange.get(&exchange).cloned();
+            for calendar_ref in calendars.by_exchange.values_mut() {
+                if previous.as_ref().is_some_and(|previous: &Arc<TradingCalendar>| Arc::ptr_eq(previous, calendar_ref)) {
+                    *calendar_ref = calendar.clone();
+                }
+            }
+            calendars.by_exchange.insert(exchange.clone(), calendar.clone());
+            for alias in entry.aliases {
+                calendars.by_exchange.insert(alias.to_uppercase(), calendar.clone());
+            }
+        }
+
+        Ok(calendars)
+    }
+
+    /// Calendar of `exchange`, `None` if it has none of its own.
+    pub fn get(&self, exchange: &str) -> Option<&TradingCalendar> {
+        self.by_exchange.get(&exchange.to_uppercase()).map(|calendar: &Arc<TradingCalendar>| calendar.as_ref())
+    }
+
+    pub fn for_exchange(&self, exchange: &str) -> &TradingCalendar {
+        self.get(exchange).unwrap_or(&self.default)
+    }
+}
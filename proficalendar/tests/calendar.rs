@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use proficalendar::{Calendars, TradingCalendar};
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+}
+
+#[test]
+fn us_holidays_follow_nyse_rules() {
+    let calendars = Calendars::builtin();
+    let holidays: BTreeSet<NaiveDate> = calendars.for_exchange("nasdaq").holidays(2022);
+
+    assert_eq!(holidays, [
+        date(2022, 1, 17),
+        date(2022, 2, 21),
+        date(2022, 4, 15),
+        date(2022, 5, 30),
+        date(2022, 6, 20),
+        date(2022, 7, 4),
+        date(2022, 9, 5),
+        date(2022, 11, 24),
+        date(2022, 12, 26),
+    ].into_iter().collect());
+}
+
+#[test]
+fn weekend_holidays_move_to_free_weekdays() {
+    let calendars = Calendars::builtin();
+
+    // Christmas and Boxing Day 2021 fell on a weekend
+    let lse: &TradingCalendar = calendars.for_exchange("LSE");
+    assert!(!lse.is_trading_day(date(2021, 12, 27)));
+    assert!(!lse.is_trading_day(date(2021, 12, 28)));
+    assert!(lse.is_trading_day(date(2021, 12, 29)));
+
+    // Constitution Day 2020 was a Sunday and the next two days were holidays already
+    let tse: &TradingCalendar = calendars.for_exchange("TSE");
+    assert!(!tse.is_trading_day(date(2020, 5, 6)));
+    assert!(tse.is_trading_day(date(2020, 5, 7)));
+    assert!(!tse.is_trading_day(date(2024, 3, 20)));
+}
+
+#[test]
+fn tse_observes_the_emperors_birthday_of_its_era() {
+    let calendars = Calendars::builtin();
+    let tse: &TradingCalendar = calendars.for_exchange("TSE");
+
+    assert!(!tse.is_trading_day(date(1998, 12, 23)));
+    // A Sunday in 2018, made up for on Monday
+    assert!(!tse.is_trading_day(date(2018, 12, 24)));
+    // Moved to February 23rd with the Reiwa era
+    assert!(tse.is_trading_day(date(2019, 12, 23)));
+    assert!(tse.is_trading_day(date(1988, 12, 23)));
+    assert!(!tse.is_trading_day(date(2024, 2, 23)));
+}
+
+#[test]
+fn tse_happy_monday_holidays_follow_their_years() {
+    let calendars = Calendars::builtin();
+    let tse: &TradingCalendar = calendars.for_exchange("TSE");
+
+    // Coming of Age Day was on January 15th until 1999
+    assert!(!tse.is_trading_day(date(1999, 1, 15)));
+    assert!(tse.is_trading_day(date(1999, 1, 11)));
+    assert!(!tse.is_trading_day(date(2000, 1, 10)));
+    // Marine Day was on July 20th from 1996 to 2002, a Saturday in 2002
+    assert!(!tse.is_trading_day(date(2002, 7, 20)));
+    assert!(tse.is_trading_day(date(2002, 7, 15)));
+    assert!(!tse.is_trading_day(date(2001, 7, 20)));
+    assert!(tse.is_trading_day(date(1995, 7, 20)));
+    // Respect for the Aged and Sports Days
+    assert!(!tse.is_trading_day(date(2000, 9, 15)));
+    assert!(tse.is_trading_day(date(2000, 9, 18)));
+    assert!(!tse.is_trading_day(date(1997, 10, 10)));
+    assert!(tse.is_trading_day(date(1997, 10, 13)));
+}
+
+#[test]
+fn tse_closes_between_two_holidays() {
+    let calendars = Calendars::builtin();
+    let tse: &TradingCalendar = calendars.for_exchange("TSE");
+
+    // Between Respect for the Aged Day and the autumn equinox
+    assert!(!tse.is_trading_day(date(2026, 9, 22)));
+    assert!(tse.is_trading_day(date(2026, 9, 24)));
+    // May 4th was only a holiday as a day between two before 2007
+    assert!(!tse.is_trading_day(date(1999, 5, 4)));
+    assert!(tse.is_trading_day(date(1984, 5, 4)));
+}
+
+#[test]
+fn equinox_days_before_1980() {
+    let calendars = Calendars::builtin();
+    let tse: &TradingCalendar = calendars.for_exchange("TSE");
+
+    assert!(!tse.is_trading_day(date(1979, 3, 21)));
+    assert!(tse.is_trading_day(date(1979, 3, 20)));
+    assert!(!tse.is_trading_day(date(1979, 9, 24)));
+    assert!(!tse.is_trading_day(date(1975, 3, 21)));
+}
+
+#[test]
+fn latest_session_waits_for_the_close() {
+    let calendars = Calendars::builtin();
+    let nyse: &TradingCalendar = calendars.for_exchange("NYSE");
+
+    // 15:30 and 16:30 in New York on Wednesday, January 10th
+    assert_eq!(nyse.latest_session(utc(2024, 1, 10, 20, 30)), date(2024, 1, 9));
+    assert_eq!(nyse.latest_session(utc(2024, 1, 10, 21, 30)), date(2024, 1, 10));
+    // Weekends and holidays fall back to the last session
+    assert_eq!(nyse.latest_session(utc(2024, 1, 14, 12, 0)), date(2024, 1, 12));
+    assert_eq!(nyse.latest_session(utc(2024, 1, 16, 12, 0)), date(2024, 1, 12));
+
+    // Tokyo closes at 06:30 UTC, the same UTC day
+    assert_eq!(calendars.for_exchange("TSE").latest_session(utc(2024, 1, 10, 7, 0)), date(2024, 1, 10));
+    // Sydney is already on Thursday while UTC is still on Wednesday evening
+    assert_eq!(calendars.for_exchange("ASX").latest_session(utc(2024, 1, 10, 23, 0)), date(2024, 1, 10));
+    assert_eq!(calendars.for_exchange("ASX").local_date(utc(2024, 1, 10, 23, 0)), date(2024, 1, 11));
+
+    // Unknown exchanges expect yesterday's bar in UTC
+    assert_eq!(calendars.for_exchange("OTC").latest_session(utc(2024, 1, 10, 12, 0)), date(2024, 1, 9));
+}
+
+#[test]
+fn calendar_files_extend_the_builtin_calendars() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "calendars", "calendars.json"].iter().collect();
+    let calendars = Calendars::load(&path).unwrap();
+
+    // Lunar New Year is only known from the file, the rules still apply
+    let hkex: &TradingCalendar = calendars.for_exchange("HKEX");
+    assert_eq!(hkex.trading_days(date(2024, 2, 9), date(2024, 2, 14)), vec![date(2024, 2, 9), date(2024, 2, 14)]);
+    assert!(!hkex.is_trading_day(date(2024, 12, 25)));
+
+    let tadawul: &TradingCalendar = calendars.for_exchange("sau");
+    assert_eq!(tadawul.timezone, chrono_tz::Asia::Riyadh);
+    assert_eq!(tadawul.trading_days(date(2024, 4, 7), date(2024, 4, 13)), vec![
+        date(2024, 4, 7),
+        date(2024, 4, 8),
+        date(2024, 4, 9),
+        date(2024, 4, 11),
+    ]);
+
+    assert!(Calendars::load(&path.with_file_name("missing.json")).is_err());
+}
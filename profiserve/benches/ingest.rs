@@ -0,0 +1,92 @@
+//! Full-history ingestion of 20k daily bars into each storage backend.
+//!
+//! Run with `cargo bench --bench ingest`.
+
+use std::time::{Duration, Instant};
+
+use chrono::{Days, NaiveDate};
+use profiserve::models::{BatchMode, HistoricalDataPoint, HistoryQuery, Stock};
+use profiserve::storage::{MemoryStorage, SqliteStorage, Storage};
+
+const BAR_COUNT: usize = 20_000;
+const TICKER: &str = "BENCH";
+
+/// Bars on consecutive days starting in 1900.
+fn bars() -> Vec<HistoricalDataPoint> {
+    let first_date: NaiveDate = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+
+    (0..BAR_COUNT)
+        .map(|i: usize| {
+            let price: f64 = 100.0 + (i % 50) as f64;
+
+            HistoricalDataPoint {
+                date: first_date + Days::new(i as u64),
+                open: price,
+                high: price + 1.0,
+                low: price - 1.0,
+                close: price + 0.5,
+                volume: 1_000 + i as u64,
+            }
+        })
+        .collect()
+}
+
+fn report(backend: &str, operation: &str, elapsed: Duration) {
+    println!("{:<8} {:<36} {:>10.2} ms", backend, operation, elapsed.as_secs_f64() * 1000.0);
+}
+
+async fn with_stock(storage: Box<dyn Storage>) -> Box<dyn Storage> {
+    let stock: Stock = Stock {
+        ticker: TICKER.to_string(),
+        stock_exchange: "NYSE".to_string(),
+        ..Stock::default()
+    };
+    storage.upsert_stock(&stock).await.unwrap();
+    storage
+}
+
+async fn bench_backend(backend: &str, make_storage: impl Fn() -> Box<dyn Storage>) {
+    let bars: Vec<HistoricalDataPoint> = bars();
+
+    let storage: Box<dyn Storage> = with_stock(make_storage()).await;
+    let start: Instant = Instant::now();
+    for bar in &bars {
+        storage.insert_data_point(TICKER, bar).await.unwrap();
+    }
+    report(backend, "insert 20k bars one by one", start.elapsed());
+
+    let storage: Box<dyn Storage> = with_stock(make_storage()).await;
+    let start: Instant = Instant::now();
+    storage.write_data_points(TICKER, &bars, BatchMode::Insert).await.unwrap();
+    report(backend, "insert 20k bars in one batch", start.elapsed());
+
+    let start: Instant = Instant::now();
+    storage.write_data_points(TICKER, &bars, BatchMode::Upsert).await.unwrap();
+    report(backend, "re-upsert 20k unchanged bars", start.elapsed());
+
+    let start: Instant = Instant::now();
+    for bar in bars.iter().step_by(10) {
+        storage.get_data_point(TICKER, bar.date).await.unwrap();
+    }
+    report(backend, "2k point lookups", start.elapsed());
+
+    let query: HistoryQuery = HistoryQuery {
+        from: NaiveDate::from_ymd_opt(1950, 1, 1),
+        to: NaiveDate::from_ymd_opt(1950, 12, 31),
+        ..HistoryQuery::default()
+    };
+    let start: Instant = Instant::now();
+    for _ in 0..1_000 {
+        storage.get_historical_data(TICKER, &query).await.unwrap();
+    }
+    report(backend, "1k one-year range scans", start.elapsed());
+}
+
+fn main() {
+    let runtime: tokio::runtime::Runtime = tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        bench_backend("memory", || Box::new(MemoryStorage::new())).await;
+        bench_backend("sqlite", || Box::new(SqliteStorage::open(":memory:").unwrap())).await;
+    });
+}
@@ -0,0 +1,127 @@
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+use crate::request_id;
+use crate::storage::StorageError;
+use crate::validation::FieldError;
+
+/// Error returned by every handler, rendered as a JSON [`ErrorBody`].
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Option<Value>,
+}
+
+/// Body of every error response.
+#[derive(Serialize)]
+pub struct ErrorBody {
+    /// Stable, machine-readable error kind such as `not_found`.
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+    pub request_id: Option<String>,
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body: ErrorBody = ErrorBody {
+            code: self.code,
+            message: self.message,
+            details: self.details,
+            request_id: request_id::current(),
+        };
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::Conflict(message) => ApiError::conflict(message),
+            StorageError::StockNotFound(ticker) => ApiError::not_found(format!("stock {} not found", ticker)),
+            StorageError::HasHistory(ticker, data_points) => ApiError::conflict(format!(
+                "stock {} still has {} historical data points, splits or dividends, delete it with cascade=true to remove them too",
+                ticker, data_points
+            )),
+            StorageError::Database(e) => {
+                eprintln!("Storage error: {}", e);
+                ApiError::internal("storage failure")
+            }
+            StorageError::Task(e) => {
+                eprintln!("Storage error: {}", e);
+                ApiError::internal("storage failure")
+            }
+        }
+    }
+}
+
+impl From<FieldError> for ApiError {
+    fn from(error: FieldError) -> Self {
+        ApiError::unprocessable(format!("invalid {}", error.field)).with_details(error)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(_) => ApiError::unprocessable(rejection.body_text()),
+            _ => ApiError::bad_request(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::bad_request(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::bad_request(rejection.body_text())
+    }
+}
@@ -0,0 +1,33 @@
+//! Detection of missing trading days in stored history.
+
+use std::collections::HashSet;
+use chrono::NaiveDate;
+use proficalendar::TradingCalendar;
+use crate::models::HistoryGap;
+
+/// Groups the trading days of `calendar` between `from` and `to` that have
+/// no stored date into runs. A run only ends at a trading day that is
+/// stored, so a Friday and the following Monday make up a single gap.
+pub fn find_gaps(stored: &[NaiveDate], from: NaiveDate, to: NaiveDate, calendar: &TradingCalendar) -> Vec<HistoryGap> {
+    let stored: HashSet<NaiveDate> = stored.iter().copied().collect();
+    let mut gaps: Vec<HistoryGap> = Vec::new();
+    let mut current: Option<HistoryGap> = None;
+
+    for date in calendar.trading_days(from, to) {
+        if stored.contains(&date) {
+            gaps.extend(current.take());
+            continue;
+        }
+
+        match current.as_mut() {
+            Some(gap) => {
+                gap.to = date;
+                gap.missing += 1;
+            }
+            None => current = Some(HistoryGap { from: date, to: date, missing: 1 }),
+        }
+    }
+    gaps.extend(current);
+
+    gaps
+}
@@ -0,0 +1,150 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use crate::error::{ApiError, ApiResult};
+use crate::extract::{Json, Path, Query};
+use crate::models::{DateRangeQuery, Dividend, DividendList, Split, SplitList};
+use crate::validation::ValidatedJson;
+use crate::state::AppState;
+
+pub async fn get_splits(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    Query(range): Query<DateRangeQuery>,
+) -> ApiResult<Json<SplitList>> {
+    let ticker: String = ticker.to_uppercase();
+    ensure_stock_exists(&state, &ticker).await?;
+
+    let splits: Vec<Split> = state.storage.get_splits(&ticker, &range).await?;
+
+    Ok(Json(SplitList { ticker, splits }))
+}
+
+pub async fn create_split(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    ValidatedJson(split): ValidatedJson<Split>,
+) -> ApiResult<(StatusCode, Json<Split>)> {
+    let ticker: String = ticker.to_uppercase();
+
+    let inserted: bool = state.storage.insert_split(&ticker, &split).await?;
+
+    // Like data points, a split already recorded for that date is left as is
+    let status: StatusCode = if inserted { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(split)))
+}
+
+pub async fn get_split(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<Json<Split>> {
+    let ticker: String = ticker.to_uppercase();
+
+    state.storage.get_split(&ticker, date).await?
+        .map(Json)
+        .ok_or_else(|| no_corporate_action("split", &ticker, date))
+}
+
+pub async fn update_split(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+    ValidatedJson(split): ValidatedJson<Split>,
+) -> ApiResult<Json<Split>> {
+    let ticker: String = ticker.to_uppercase();
+
+    if state.storage.update_split(&ticker, date, &split).await? {
+        Ok(Json(split))
+    } else {
+        Err(no_corporate_action("split", &ticker, date))
+    }
+}
+
+pub async fn delete_split(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<StatusCode> {
+    let ticker: String = ticker.to_uppercase();
+
+    if state.storage.delete_split(&ticker, date).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(no_corporate_action("split", &ticker, date))
+    }
+}
+
+pub async fn get_dividends(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    Query(range): Query<DateRangeQuery>,
+) -> ApiResult<Json<DividendList>> {
+    let ticker: String = ticker.to_uppercase();
+    ensure_stock_exists(&state, &ticker).await?;
+
+    let dividends: Vec<Dividend> = state.storage.get_dividends(&ticker, &range).await?;
+
+    Ok(Json(DividendList { ticker, dividends }))
+}
+
+pub async fn create_dividend(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    ValidatedJson(dividend): ValidatedJson<Dividend>,
+) -> ApiResult<(StatusCode, Json<Dividend>)> {
+    let ticker: String = ticker.to_uppercase();
+
+    let inserted: bool = state.storage.insert_dividend(&ticker, &dividend).await?;
+
+    let status: StatusCode = if inserted { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(dividend)))
+}
+
+pub async fn get_dividend(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<Json<Dividend>> {
+    let ticker: String = ticker.to_uppercase();
+
+    state.storage.get_dividend(&ticker, date).await?
+        .map(Json)
+        .ok_or_else(|| no_corporate_action("dividend", &ticker, date))
+}
+
+pub async fn update_dividend(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+    ValidatedJson(dividend): ValidatedJson<Dividend>,
+) -> ApiResult<Json<Dividend>> {
+    let ticker: String = ticker.to_uppercase();
+
+    if state.storage.update_dividend(&ticker, date, &dividend).await? {
+        Ok(Json(dividend))
+    } else {
+        Err(no_corporate_action("dividend", &ticker, date))
+    }
+}
+
+pub async fn delete_dividend(
+    State(state): State<AppState>,
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<StatusCode> {
+    let ticker: String = ticker.to_uppercase();
+
+    if state.storage.delete_dividend(&ticker, date).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(no_corporate_action("dividend", &ticker, date))
+    }
+}
+
+/// Unlike history, a stock without corporate actions gets an empty list, so
+/// only an unknown ticker is reported as not found.
+async fn ensure_stock_exists(state: &AppState, ticker: &str) -> ApiResult<()> {
+    match state.storage.get_stock(ticker).await? {
+        Some(_) => Ok(()),
+        None => Err(ApiError::not_found(format!("stock {} not found", ticker))),
+    }
+}
+
+fn no_corporate_action(kind: &str, ticker: &str, date: NaiveDate) -> ApiError {
+    ApiError::not_found(format!("no {} for {} on {}", kind, ticker, date))
+}
@@ -1,102 +1,219 @@
-use std::collections::HashMap;
-use std::sync::MutexGuard;
-
 use axum::extract::State;
-use axum::{
-    extract::Path,
-    http::StatusCode,
-    Json,
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::error::{ApiError, ApiResult};
+use crate::extract::{Json, Path, Query};
+use proficalendar::TradingCalendar;
+use crate::gaps;
+use crate::models::{
+    HistoricalDataPoint, HistoricalDataList, HistoricalDateList, HistoryGap, HistoryGapList, HistoryQuery, DateRangeQuery,
+    BatchQuery, BatchReport, BatchItemResult, BatchItemStatus, Stock,
 };
-use crate::models::{HistoricalDataPoint, HistoricalDataList};
+use crate::storage::HistoryPage;
+use crate::validation::{Validate, ValidatedJson, FieldError};
 use crate::state::AppState;
 
 pub async fn get_historical_data(
     State(state): State<AppState>,
     Path(ticker): Path<String>,
-) -> Result<Json<HistoricalDataList>, StatusCode> {
-    let historical_data: MutexGuard<HashMap<String, Vec<HistoricalDataPoint>>> = state.historical_data.lock().unwrap();
+    Query(query): Query<HistoryQuery>,
+) -> ApiResult<Json<HistoricalDataList>> {
     let ticker: String = ticker.to_uppercase();
+    query.validate()?;
 
-    historical_data.get(&ticker)
-        .cloned()
-        .map(|data: Vec<HistoricalDataPoint>| Json(HistoricalDataList { ticker: ticker.clone(), data }))
-        .ok_or(StatusCode::NOT_FOUND)
+    let page: HistoryPage = state.storage.get_historical_data(&ticker, &query).await?
+        .ok_or_else(|| no_history(&ticker))?;
+
+    let next_cursor: Option<NaiveDate> = if page.has_more {
+        page.data.last().map(|dp: &HistoricalDataPoint| dp.date)
+    } else {
+        None
+    };
+
+    Ok(Json(HistoricalDataList {
+        ticker,
+        data: page.data,
+        total: page.total,
+        next_cursor,
+    }))
+}
+
+pub async fn get_latest_historical_data_point(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+) -> ApiResult<Json<HistoricalDataPoint>> {
+    let ticker: String = ticker.to_uppercase();
+
+    state.storage.get_latest_data_point(&ticker).await?
+        .map(Json)
+        .ok_or_else(|| no_history(&ticker))
+}
+
+pub async fn get_historical_dates(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    Query(range): Query<DateRangeQuery>,
+) -> ApiResult<Json<HistoricalDateList>> {
+    let ticker: String = ticker.to_uppercase();
+
+    let dates: Vec<NaiveDate> = state.storage.get_dates(&ticker, &range).await?
+        .ok_or_else(|| no_history(&ticker))?;
+
+    Ok(Json(HistoricalDateList { ticker, dates }))
+}
+
+/// Lists the runs of trading days missing from the stored history, between
+/// its first and last stored dates unless `from` or `to` are given. Trading
+/// days follow the calendar of the stock's exchange.
+pub async fn get_historical_gaps(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    Query(range): Query<DateRangeQuery>,
+) -> ApiResult<Json<HistoryGapList>> {
+    let ticker: String = ticker.to_uppercase();
+
+    let dates: Vec<NaiveDate> = state.storage.get_dates(&ticker, &range).await?
+        .ok_or_else(|| no_history(&ticker))?;
+    let stock_exchange: String = state.storage.get_stock(&ticker).await?
+        .map(|stock: Stock| stock.stock_exchange)
+        .unwrap_or_default();
+    let calendar: &TradingCalendar = state.calendars.for_exchange(&stock_exchange);
+
+    let from: Option<NaiveDate> = range.from.or_else(|| dates.first().copied());
+    let to: Option<NaiveDate> = range.to.or_else(|| dates.last().copied());
+
+    let (expected, gaps): (usize, Vec<HistoryGap>) = match (from, to) {
+        (Some(from), Some(to)) => (
+            calendar.trading_days(from, to).len(),
+            gaps::find_gaps(&dates, from, to, calendar),
+        ),
+        _ => (0, Vec::new()),
+    };
+    let missing: usize = gaps.iter().map(|gap: &HistoryGap| gap.missing).sum();
+
+    Ok(Json(HistoryGapList {
+        ticker,
+        from,
+        to,
+        expected,
+        stored: expected - missing,
+        gaps,
+    }))
 }
 
 pub async fn create_historical_data(
     State(state): State<AppState>,
     Path(ticker): Path<String>,
-    Json(data_point): Json<HistoricalDataPoint>,
-) -> (StatusCode, Json<HistoricalDataPoint>) {
-    let mut historical_data: MutexGuard<HashMap<String, Vec<HistoricalDataPoint>>> = state.historical_data.lock().unwrap();
+    ValidatedJson(data_point): ValidatedJson<HistoricalDataPoint>,
+) -> ApiResult<(StatusCode, Json<HistoricalDataPoint>)> {
     let ticker: String = ticker.to_uppercase();
 
-    let data_points = historical_data.entry(ticker)
-        .or_insert_with(Vec::<HistoricalDataPoint>::new);
-    
-    // Check if this date already exists
-    if data_points.iter().any(|dp| dp.date == data_point.date) {
+    let inserted: bool = state.storage.insert_data_point(&ticker, &data_point).await?;
+
+    if inserted {
+        Ok((StatusCode::CREATED, Json(data_point)))
+    } else {
         // Date already exists, return OK without adding duplicate
-        return (StatusCode::OK, Json(data_point));
+        Ok((StatusCode::OK, Json(data_point)))
     }
-    
-    // Add the new data point
-    data_points.push(data_point.clone());
-    (StatusCode::CREATED, Json(data_point))
+}
+
+pub async fn create_historical_data_batch(
+    State(state): State<AppState>,
+    Path(ticker): Path<String>,
+    Query(query): Query<BatchQuery>,
+    Json(items): Json<Vec<Value>>,
+) -> ApiResult<Json<BatchReport>> {
+    let ticker: String = ticker.to_uppercase();
+
+    // Items are deserialized and validated one by one so that a malformed
+    // one is reported as invalid instead of rejecting the whole batch
+    let parsed: Vec<Result<HistoricalDataPoint, BatchItemResult>> = items.into_iter()
+        .map(parse_batch_item)
+        .collect();
+    let valid_data_points: Vec<HistoricalDataPoint> = parsed.iter()
+        .filter_map(|item: &Result<HistoricalDataPoint, BatchItemResult>| item.as_ref().ok())
+        .cloned()
+        .collect();
+
+    let mut written = state.storage.write_data_points(&ticker, &valid_data_points, query.mode).await?
+        .into_iter();
+
+    let results: Vec<BatchItemResult> = parsed.into_iter()
+        .map(|item: Result<HistoricalDataPoint, BatchItemResult>| match item {
+            Ok(_) => written.next().expect("one result per written data point"),
+            Err(invalid) => invalid,
+        })
+        .collect();
+
+    Ok(Json(BatchReport::new(ticker, results)))
+}
+
+/// Deserializes and validates one item of a batch, or describes why it is
+/// invalid. The date is reported whenever the item has a readable one.
+fn parse_batch_item(item: Value) -> Result<HistoricalDataPoint, BatchItemResult> {
+    let date: Option<NaiveDate> = item.get("date")
+        .and_then(|date: &Value| NaiveDate::deserialize(date).ok());
+
+    let error: FieldError = match serde_path_to_error::deserialize::<_, HistoricalDataPoint>(item) {
+        Ok(data_point) => match data_point.validate() {
+            Ok(()) => return Ok(data_point),
+            Err(e) => e,
+        },
+        Err(e) => FieldError::new(&e.path().to_string(), e.inner().to_string()),
+    };
+
+    Err(BatchItemResult {
+        date,
+        status: BatchItemStatus::Invalid,
+        reason: Some(error.to_string()),
+    })
 }
 
 pub async fn get_historical_data_point(
     State(state): State<AppState>,
-    Path((ticker, date)): Path<(String, String)>,
-) -> Result<Json<HistoricalDataPoint>, StatusCode> {
-    let historical_data: MutexGuard<HashMap<String, Vec<HistoricalDataPoint>>> = state.historical_data.lock().unwrap();
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<Json<HistoricalDataPoint>> {
     let ticker: String = ticker.to_uppercase();
 
-    if let Some(data_points) = historical_data.get(&ticker) {
-        data_points.iter()
-            .find(|dp: &&HistoricalDataPoint| dp.date == date)
-            .cloned()
-            .map(Json)
-            .ok_or(StatusCode::NOT_FOUND)
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+    state.storage.get_data_point(&ticker, date).await?
+        .map(Json)
+        .ok_or_else(|| no_data_point(&ticker, date))
 }
 
 pub async fn update_historical_data_point(
     State(state): State<AppState>,
-    Path((ticker, date)): Path<(String, String)>,
-    Json(updated_data): Json<HistoricalDataPoint>,
-) -> Result<Json<HistoricalDataPoint>, StatusCode> {
-    let mut historical_data: MutexGuard<HashMap<String, Vec<HistoricalDataPoint>>> = state.historical_data.lock().unwrap();
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+    ValidatedJson(updated_data): ValidatedJson<HistoricalDataPoint>,
+) -> ApiResult<Json<HistoricalDataPoint>> {
     let ticker: String = ticker.to_uppercase();
 
-    if let Some(data_points) = historical_data.get_mut(&ticker) {
-        if let Some(point) = data_points.iter_mut().find(|dp: &&mut HistoricalDataPoint| dp.date == date) {
-            *point = updated_data.clone();
-            Ok(Json(updated_data))
-        } else {
-            Err(StatusCode::NOT_FOUND)
-        }
+    if state.storage.update_data_point(&ticker, date, &updated_data).await? {
+        Ok(Json(updated_data))
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Err(no_data_point(&ticker, date))
     }
 }
 
 pub async fn delete_historical_data_point(
     State(state): State<AppState>,
-    Path((ticker, date)): Path<(String, String)>,
-) -> StatusCode {
-    let mut historical_data: MutexGuard<HashMap<String, Vec<HistoricalDataPoint>>> = state.historical_data.lock().unwrap();
+    Path((ticker, date)): Path<(String, NaiveDate)>,
+) -> ApiResult<StatusCode> {
     let ticker: String = ticker.to_uppercase();
-    if let Some(data_points) = historical_data.get_mut(&ticker) {
-        if let Some(pos) = data_points.iter().position(|dp: &HistoricalDataPoint| dp.date == date) {
-            data_points.remove(pos);
-            StatusCode::NO_CONTENT
-        } else {
-            StatusCode::NOT_FOUND
-        }
+
+    if state.storage.delete_data_point(&ticker, date).await? {
+        Ok(StatusCode::NO_CONTENT)
     } else {
-        StatusCode::NOT_FOUND
+        Err(no_data_point(&ticker, date))
     }
 }
+
+fn no_history(ticker: &str) -> ApiError {
+    ApiError::not_found(format!("no historical data for {}", ticker))
+}
+
+fn no_data_point(ticker: &str, date: NaiveDate) -> ApiError {
+    ApiError::not_found(format!("no data point for {} on {}", ticker, date))
+}
@@ -1,2 +1,3 @@
 pub mod stocks;
 pub mod history;
+pub mod corporate_actions;
@@ -1,75 +1,72 @@
-use std::collections::HashMap;
-use std::sync::MutexGuard;
-
 use axum::extract::State;
-use axum::{
-    extract::Path,
-    http::StatusCode,
-    Json,
-};
-use crate::models::Stock;
+use axum::http::StatusCode;
+use crate::error::{ApiError, ApiResult};
+use crate::extract::{Json, Path, Query};
+use crate::models::{Stock, DeleteStockQuery};
 use crate::state::AppState;
+use crate::validation::ValidatedJson;
 
-pub async fn get_stocks(State(state): State<AppState>) -> Json<Vec<Stock>> {
-    let stocks: MutexGuard<HashMap<String, Stock>> = state.stocks.lock().unwrap();
-    let stock_list: Vec<Stock> = stocks.values().cloned().collect();
-    Json(stock_list)
+pub async fn get_stocks(State(state): State<AppState>) -> ApiResult<Json<Vec<Stock>>> {
+    let stocks: Vec<Stock> = state.storage.get_stocks().await?;
+    Ok(Json(stocks))
 }
 
-pub async fn create_stock(State(state): State<AppState>, Json(stock): Json<Stock>) -> (StatusCode, Json<Stock>) {
-    let mut stocks: MutexGuard<HashMap<String, Stock>> = state.stocks.lock().unwrap();
-    let ticker: String = stock.ticker.to_uppercase();
-    let stock: Stock = Stock { 
-        ticker: ticker.clone(),
-        stock_exchange: stock.stock_exchange,
-    };
-    stocks.insert(ticker, stock.clone());
-    (StatusCode::CREATED, Json(stock))
+pub async fn create_stock(
+    State(state): State<AppState>,
+    ValidatedJson(stock): ValidatedJson<Stock>,
+) -> ApiResult<(StatusCode, Json<Stock>)> {
+    let stock: Stock = normalize(stock);
+    state.storage.upsert_stock(&stock).await?;
+    Ok((StatusCode::CREATED, Json(stock)))
 }
 
 pub async fn get_stock(
     State(state): State<AppState>,
     Path(ticker): Path<String>,
-) -> Result<Json<Stock>, StatusCode> {
-    let stocks: MutexGuard<HashMap<String, Stock>> = state.stocks.lock().unwrap();
+) -> ApiResult<Json<Stock>> {
     let ticker: String = ticker.to_uppercase();
 
-    stocks.get(&ticker)
-        .cloned()
+    state.storage.get_stock(&ticker).await?
         .map(Json)
-        .ok_or(StatusCode::NOT_FOUND)
+        .ok_or_else(|| ApiError::not_found(format!("stock {} not found", ticker)))
 }
 
 pub async fn update_stock(
     State(state): State<AppState>,
     Path(ticker): Path<String>,
-    Json(updated_stock): Json<Stock>,
-) -> Result<Json<Stock>, StatusCode> {
-    let mut stocks: MutexGuard<HashMap<String, Stock>> = state.stocks.lock().unwrap();
+    ValidatedJson(updated_stock): ValidatedJson<Stock>,
+) -> ApiResult<Json<Stock>> {
     let ticker: String = ticker.to_uppercase();
+    let stock: Stock = normalize(updated_stock);
+
+    let updated: bool = state.storage.update_stock(&ticker, &stock).await?;
 
-    if stocks.contains_key(&ticker) {
-        let stock: Stock = Stock { 
-            ticker: updated_stock.ticker.to_uppercase(),
-            stock_exchange: updated_stock.stock_exchange,
-        };
-        stocks.insert(ticker, stock.clone());
+    if updated {
         Ok(Json(stock))
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Err(ApiError::not_found(format!("stock {} not found", ticker)))
     }
 }
 
 pub async fn delete_stock(
     State(state): State<AppState>,
     Path(ticker): Path<String>,
-) -> StatusCode {
-    let mut stocks: MutexGuard<HashMap<String, Stock>> = state.stocks.lock().unwrap();
+    Query(query): Query<DeleteStockQuery>,
+) -> ApiResult<StatusCode> {
     let ticker: String = ticker.to_uppercase();
 
-    if stocks.remove(&ticker).is_some() {
-        StatusCode::NO_CONTENT
+    if state.storage.delete_stock(&ticker, query.cascade).await? {
+        Ok(StatusCode::NO_CONTENT)
     } else {
-        StatusCode::NOT_FOUND
+        Err(ApiError::not_found(format!("stock {} not found", ticker)))
+    }
+}
+
+/// Uppercases the ticker. The currency keeps its case, as minor units such
+/// as `GBp` for pence differ from their currency only by it.
+fn normalize(stock: Stock) -> Stock {
+    Stock {
+        ticker: stock.ticker.to_uppercase(),
+        ..stock
     }
 }
@@ -0,0 +1,10 @@
+pub mod models;
+pub mod error;
+pub mod extract;
+pub mod gaps;
+pub mod request_id;
+pub mod storage;
+pub mod state;
+pub mod validation;
+pub mod handlers;
+pub mod routes;
@@ -1,15 +1,39 @@
-mod models;
-mod state;
-mod handlers;
-mod routes;
-
 use std::net::SocketAddr;
-use state::AppState;
-use routes::create_router;
+use std::sync::Arc;
+use proficalendar::{calendars_file, Calendars};
+use profiserve::storage::{MemoryStorage, SqliteStorage, Storage};
+use profiserve::state::AppState;
+use profiserve::routes::create_router;
 
 #[tokio::main]
 async fn main() {
-    let state: AppState = AppState::new();
+    let storage_backend: String = std::env::var("PROFISERVE_STORAGE")
+        .unwrap_or_else(|_| "sqlite".to_string());
+
+    let storage: Arc<dyn Storage> = match storage_backend.as_str() {
+        "memory" => {
+            println!("Using in-memory storage");
+            Arc::new(MemoryStorage::new())
+        }
+        "sqlite" => {
+            let database_path: String = std::env::var("PROFISERVE_DATABASE_PATH")
+                .unwrap_or_else(|_| "profiserve.db".to_string());
+
+            println!("Using database {}", database_path);
+            Arc::new(SqliteStorage::open(&database_path).unwrap())
+        }
+        other => panic!("Unknown PROFISERVE_STORAGE backend: {}", other),
+    };
+
+    let calendars: Calendars = match calendars_file() {
+        Some(path) => {
+            println!("Using trading calendars from {}", path.display());
+            Calendars::load(&path).unwrap()
+        }
+        None => Calendars::builtin(),
+    };
+
+    let state: AppState = AppState::with_calendars(storage, calendars);
     let app: axum::Router = create_router(state);
 
     let addr: SocketAddr = SocketAddr::from(([127, 0, 0, 1], 3000));
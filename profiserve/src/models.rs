@@ -1,14 +1,82 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Stock {
     pub ticker: String,
     pub stock_exchange: String,
+    /// Display name, e.g. "Apple Inc."
+    #[serde(default)]
+    pub name: Option<String>,
+    /// ISO 4217 code of the currency the stock is quoted in, or of its minor
+    /// unit as Yahoo writes it, such as `GBp` for pence.
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub asset_class: Option<AssetClass>,
+    #[serde(default)]
+    pub sector: Option<String>,
+    #[serde(default)]
+    pub industry: Option<String>,
+    #[serde(default)]
+    pub isin: Option<String>,
+    #[serde(default)]
+    pub figi: Option<String>,
+    #[serde(default)]
+    pub listed_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub delisted_on: Option<NaiveDate>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetClass {
+    Equity,
+    Etf,
+    Index,
+    Crypto,
+    Fx,
+}
+
+impl AssetClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetClass::Equity => "equity",
+            AssetClass::Etf => "etf",
+            AssetClass::Index => "index",
+            AssetClass::Crypto => "crypto",
+            AssetClass::Fx => "fx",
+        }
+    }
+}
+
+impl std::str::FromStr for AssetClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "equity" => Ok(AssetClass::Equity),
+            "etf" => Ok(AssetClass::Etf),
+            "index" => Ok(AssetClass::Index),
+            "crypto" => Ok(AssetClass::Crypto),
+            "fx" => Ok(AssetClass::Fx),
+            other => Err(format!("unknown asset class {}", other)),
+        }
+    }
+}
+
+/// Query parameters of `DELETE /api/v1/stocks/:ticker`.
+#[derive(Deserialize, Default)]
+pub struct DeleteStockQuery {
+    /// Also delete the historical data, splits and dividends of the stock
+    /// instead of refusing to delete a stock that still has some.
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct HistoricalDataPoint {
-    pub date: String,
+    pub date: NaiveDate,
     pub open: f64,
     pub high: f64,
     pub low: f64,
@@ -20,4 +88,163 @@ pub struct HistoricalDataPoint {
 pub struct HistoricalDataList {
     pub ticker: String,
     pub data: Vec<HistoricalDataPoint>,
+    /// Number of data points within the requested date range, across all pages.
+    pub total: usize,
+    /// Cursor to pass back to fetch the next page, `None` on the last page.
+    pub next_cursor: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct HistoricalDateList {
+    pub ticker: String,
+    pub dates: Vec<NaiveDate>,
+}
+
+/// Consecutive trading days, both inclusive, without stored data.
+#[derive(Serialize)]
+pub struct HistoryGap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Number of trading days missing.
+    pub missing: usize,
+}
+
+/// Response of `GET /api/v1/stocks/:ticker/history/gaps`.
+#[derive(Serialize)]
+pub struct HistoryGapList {
+    pub ticker: String,
+    /// Range checked, the stored history's own bounds unless asked otherwise.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Trading days expected within the range.
+    pub expected: usize,
+    /// Trading days within the range with stored data.
+    pub stored: usize,
+    pub gaps: Vec<HistoryGap>,
+}
+
+/// A stock split effective on `date`, where `denominator` shares became
+/// `numerator` shares, such as 4 for 1.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Split {
+    pub date: NaiveDate,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// A cash dividend per share, in the stock's currency, dated on its ex-dividend date.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Dividend {
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+#[derive(Serialize)]
+pub struct SplitList {
+    pub ticker: String,
+    pub splits: Vec<Split>,
+}
+
+#[derive(Serialize)]
+pub struct DividendList {
+    pub ticker: String,
+    pub dividends: Vec<Dividend>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters of `GET /api/v1/stocks/:ticker/history`.
+///
+/// `from` and `to` are inclusive. `cursor` is the `next_cursor` of a previous
+/// page and resumes right after the last date of that page, in the requested order.
+#[derive(Deserialize, Clone, Default)]
+pub struct HistoryQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub cursor: Option<NaiveDate>,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// Query parameters of `GET /api/v1/stocks/:ticker/history/dates` and of the
+/// splits and dividends lists, both bounds are inclusive.
+#[derive(Deserialize, Default)]
+pub struct DateRangeQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchMode {
+    /// Only add data points for new dates, existing ones are left untouched.
+    #[default]
+    Insert,
+    /// Add data points for new dates and overwrite the ones that differ.
+    Upsert,
+}
+
+/// Query parameters of `POST /api/v1/stocks/:ticker/history/batch`.
+#[derive(Deserialize, Default)]
+pub struct BatchQuery {
+    #[serde(default)]
+    pub mode: BatchMode,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemStatus {
+    Created,
+    Updated,
+    Unchanged,
+    /// A different data point already exists for that date and the batch was sent in insert mode.
+    Conflicted,
+    Invalid,
+}
+
+#[derive(Serialize)]
+pub struct BatchItemResult {
+    /// Missing for an invalid item whose date could not be read.
+    pub date: Option<NaiveDate>,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchReport {
+    pub ticker: String,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub conflicted: usize,
+    pub invalid: usize,
+    /// One result per submitted data point, in submission order.
+    pub results: Vec<BatchItemResult>,
+}
+
+impl BatchReport {
+    pub fn new(ticker: String, results: Vec<BatchItemResult>) -> Self {
+        let count = |status: BatchItemStatus| results.iter()
+            .filter(|result: &&BatchItemResult| result.status == status)
+            .count();
+
+        Self {
+            ticker,
+            created: count(BatchItemStatus::Created),
+            updated: count(BatchItemStatus::Updated),
+            unchanged: count(BatchItemStatus::Unchanged),
+            conflicted: count(BatchItemStatus::Conflicted),
+            invalid: count(BatchItemStatus::Invalid),
+            results,
+        }
+    }
 }
@@ -0,0 +1,33 @@
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Returns the id of the request being handled, if called from within [`assign`].
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Middleware giving every request an id, taken from its `x-request-id`
+/// header when present, and echoing it back in the response headers.
+pub async fn assign(request: Request, next: Next) -> Response {
+    let request_id: String = request.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value: &HeaderValue| value.to_str().ok())
+        .map(String::from)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut response: Response = REQUEST_ID.scope(request_id.clone(), next.run(request)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
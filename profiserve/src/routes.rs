@@ -1,5 +1,7 @@
-use axum::{routing::get, Router};
-use crate::handlers::{stocks, history};
+use axum::{middleware, routing::{get, post}, Router};
+use crate::error::ApiError;
+use crate::handlers::{stocks, history, corporate_actions};
+use crate::request_id;
 use crate::state::AppState;
 
 pub fn create_router(state: AppState) -> Router {
@@ -20,11 +22,51 @@ pub fn create_router(state: AppState) -> Router {
             get(history::get_historical_data)
             .post(history::create_historical_data)
         )
+        .route(
+            "/api/v1/stocks/:ticker/history/batch",
+            post(history::create_historical_data_batch)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/history/latest",
+            get(history::get_latest_historical_data_point)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/history/dates",
+            get(history::get_historical_dates)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/history/gaps",
+            get(history::get_historical_gaps)
+        )
         .route(
             "/api/v1/stocks/:ticker/history/:date",
             get(history::get_historical_data_point)
             .put(history::update_historical_data_point)
             .delete(history::delete_historical_data_point)
         )
+        .route(
+            "/api/v1/stocks/:ticker/splits",
+            get(corporate_actions::get_splits)
+            .post(corporate_actions::create_split)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/splits/:date",
+            get(corporate_actions::get_split)
+            .put(corporate_actions::update_split)
+            .delete(corporate_actions::delete_split)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/dividends",
+            get(corporate_actions::get_dividends)
+            .post(corporate_actions::create_dividend)
+        )
+        .route(
+            "/api/v1/stocks/:ticker/dividends/:date",
+            get(corporate_actions::get_dividend)
+            .put(corporate_actions::update_dividend)
+            .delete(corporate_actions::delete_dividend)
+        )
+        .fallback(|| async { ApiError::not_found("no such route") })
+        .layer(middleware::from_fn(request_id::assign))
         .with_state(state)
 }
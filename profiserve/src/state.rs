@@ -1,21 +1,23 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use crate::models::{Stock, HistoricalDataPoint};
-
-pub type StockStore = Arc<Mutex<HashMap<String, Stock>>>;
-pub type HistoricalDataStore = Arc<Mutex<HashMap<String, Vec<HistoricalDataPoint>>>>;
+use std::sync::Arc;
+use proficalendar::Calendars;
+use crate::storage::Storage;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub stocks: StockStore,
-    pub historical_data: HistoricalDataStore,
+    pub storage: Arc<dyn Storage>,
+    pub calendars: Arc<Calendars>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    /// State using the built-in trading calendars.
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self::with_calendars(storage, Calendars::builtin())
+    }
+
+    pub fn with_calendars(storage: Arc<dyn Storage>, calendars: Calendars) -> Self {
         Self {
-            stocks: Arc::new(Mutex::new(HashMap::<String, Stock>::new())),
-            historical_data: Arc::new(Mutex::new(HashMap::<String, Vec<HistoricalDataPoint>>::new())),
+            storage,
+            calendars: Arc::new(calendars),
         }
     }
 }
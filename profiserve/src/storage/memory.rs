@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use crate::models::{Stock, HistoricalDataPoint, HistoryQuery, SortOrder, DateRangeQuery, BatchMode, BatchItemStatus, BatchItemResult, Split, Dividend};
+use super::{batch_item_status, HistoryPage, Storage, StorageError, StorageResult};
+
+pub type StockStore = Mutex<HashMap<String, Stock>>;
+/// Data points of a single ticker, keyed and therefore ordered by date.
+///
+/// Never empty: a ticker without data points has no entry, like in SQLite.
+pub type TickerHistory = BTreeMap<NaiveDate, HistoricalDataPoint>;
+pub type HistoricalDataStore = Mutex<HashMap<String, TickerHistory>>;
+/// Corporate actions of each ticker, keyed by date.
+pub type CorporateActionStore<T> = Mutex<HashMap<String, BTreeMap<NaiveDate, T>>>;
+
+/// Non-persistent storage, everything is lost when the server stops.
+///
+/// Methods that need several maps always lock them in the order of the
+/// fields: `stocks`, `historical_data`, `splits` then `dividends`.
+#[derive(Default)]
+pub struct MemoryStorage {
+    stocks: StockStore,
+    historical_data: HistoricalDataStore,
+    splits: CorporateActionStore<Split>,
+    dividends: CorporateActionStore<Dividend>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Date bounds of a range scan, `None` if they select nothing, in which case
+/// they must not be passed to `BTreeMap::range` as it would panic.
+fn date_bounds(lower: Bound<NaiveDate>, upper: Bound<NaiveDate>) -> Option<(Bound<NaiveDate>, Bound<NaiveDate>)> {
+    let date = |bound: Bound<NaiveDate>| match bound {
+        Bound::Included(date) | Bound::Excluded(date) => Some(date),
+        Bound::Unbounded => None,
+    };
+
+    if let (Some(l), Some(u)) = (date(lower), date(upper)) {
+        let excluded: bool = matches!(lower, Bound::Excluded(_)) || matches!(upper, Bound::Excluded(_));
+        if l > u || (l == u && excluded) {
+            return None;
+        }
+    }
+
+    Some((lower, upper))
+}
+
+fn inclusive_bound(date: Option<NaiveDate>) -> Bound<NaiveDate> {
+    date.map_or(Bound::Unbounded, Bound::Included)
+}
+
+/// Moves the entry of `ticker` in `map`, if any, to `new_ticker`.
+fn rename<T>(map: &mut HashMap<String, T>, ticker: &str, new_ticker: &str) {
+    if let Some(value) = map.remove(ticker) {
+        map.insert(new_ticker.to_string(), value);
+    }
+}
+
+fn actions_in_range<T: Clone>(store: &CorporateActionStore<T>, ticker: &str, range: &DateRangeQuery) -> Vec<T> {
+    let actions: MutexGuard<HashMap<String, BTreeMap<NaiveDate, T>>> = store.lock().unwrap();
+
+    match (actions.get(ticker), date_bounds(inclusive_bound(range.from), inclusive_bound(range.to))) {
+        (Some(actions), Some(bounds)) => actions.range(bounds).map(|(_, action)| action.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn get_action<T: Clone>(store: &CorporateActionStore<T>, ticker: &str, date: NaiveDate) -> Option<T> {
+    let actions: MutexGuard<HashMap<String, BTreeMap<NaiveDate, T>>> = store.lock().unwrap();
+
+    actions.get(ticker).and_then(|actions: &BTreeMap<NaiveDate, T>| actions.get(&date)).cloned()
+}
+
+/// Inserts `action` on `date` unless `ticker` already has one then.
+fn insert_action<T: Clone>(store: &CorporateActionStore<T>, ticker: &str, date: NaiveDate, action: &T) -> bool {
+    let mut actions: MutexGuard<HashMap<String, BTreeMap<NaiveDate, T>>> = store.lock().unwrap();
+    let actions: &mut BTreeMap<NaiveDate, T> = actions.entry(ticker.to_string()).or_default();
+
+    if actions.contains_key(&date) {
+        return false;
+    }
+
+    actions.insert(date, action.clone());
+    true
+}
+
+/// Replaces the action stored on `date` with `action`, dated `new_date`.
+fn update_action<T: Clone>(
+    store: &CorporateActionStore<T>,
+    ticker: &str,
+    date: NaiveDate,
+    new_date: NaiveDate,
+    action: &T,
+) -> StorageResult<bool> {
+    let mut actions: MutexGuard<HashMap<String, BTreeMap<NaiveDate, T>>> = store.lock().unwrap();
+
+    let Some(actions) = actions.get_mut(ticker) else {
+        return Ok(false);
+    };
+
+    if !actions.contains_key(&date) {
+        return Ok(false);
+    }
+
+    if new_date != date && actions.contains_key(&new_date) {
+        return Err(StorageError::Conflict(format!("{} already has a corporate action on {}", ticker, new_date)));
+    }
+
+    actions.remove(&date);
+    actions.insert(new_date, action.clone());
+    Ok(true)
+}
+
+fn delete_action<T>(store: &CorporateActionStore<T>, ticker: &str, date: NaiveDate) -> bool {
+    let mut store: MutexGuard<HashMap<String, BTreeMap<NaiveDate, T>>> = store.lock().unwrap();
+
+    let Some(actions) = store.get_mut(ticker) else {
+        return false;
+    };
+
+    let deleted: bool = actions.remove(&date).is_some();
+    if actions.is_empty() {
+        store.remove(ticker);
+    }
+
+    deleted
+}
+
+/// Tightens an inclusive lower bound with an exclusive cursor.
+fn lower_bound(from: Option<NaiveDate>, cursor: Option<NaiveDate>) -> Bound<NaiveDate> {
+    match (from, cursor) {
+        (Some(from), Some(cursor)) if from > cursor => Bound::Included(from),
+        (_, Some(cursor)) => Bound::Excluded(cursor),
+        (from, None) => inclusive_bound(from),
+    }
+}
+
+/// Tightens an inclusive upper bound with an exclusive cursor.
+fn upper_bound(to: Option<NaiveDate>, cursor: Option<NaiveDate>) -> Bound<NaiveDate> {
+    match (to, cursor) {
+        (Some(to), Some(cursor)) if to < cursor => Bound::Included(to),
+        (_, Some(cursor)) => Bound::Excluded(cursor),
+        (to, None) => inclusive_bound(to),
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn get_stocks(&self) -> StorageResult<Vec<Stock>> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+
+        let mut stocks: Vec<Stock> = stocks.values().cloned().collect();
+        stocks.sort_by(|a: &Stock, b: &Stock| a.ticker.cmp(&b.ticker));
+        Ok(stocks)
+    }
+
+    async fn get_stock(&self, ticker: &str) -> StorageResult<Option<Stock>> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        Ok(stocks.get(ticker).cloned())
+    }
+
+    async fn upsert_stock(&self, stock: &Stock) -> StorageResult<()> {
+        let mut stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        stocks.insert(stock.ticker.clone(), stock.clone());
+        Ok(())
+    }
+
+    async fn update_stock(&self, ticker: &str, stock: &Stock) -> StorageResult<bool> {
+        let mut stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+        let mut splits: MutexGuard<HashMap<String, BTreeMap<NaiveDate, Split>>> = self.splits.lock().unwrap();
+        let mut dividends: MutexGuard<HashMap<String, BTreeMap<NaiveDate, Dividend>>> = self.dividends.lock().unwrap();
+
+        if !stocks.contains_key(ticker) {
+            return Ok(false);
+        }
+
+        if stock.ticker != ticker {
+            if stocks.contains_key(&stock.ticker) || historical_data.contains_key(&stock.ticker) {
+                return Err(StorageError::Conflict(format!("ticker {} is already in use", stock.ticker)));
+            }
+
+            stocks.remove(ticker);
+            rename(&mut historical_data, ticker, &stock.ticker);
+            rename(&mut splits, ticker, &stock.ticker);
+            rename(&mut dividends, ticker, &stock.ticker);
+        }
+
+        stocks.insert(stock.ticker.clone(), stock.clone());
+        Ok(true)
+    }
+
+    async fn delete_stock(&self, ticker: &str, cascade: bool) -> StorageResult<bool> {
+        let mut stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+        let mut splits: MutexGuard<HashMap<String, BTreeMap<NaiveDate, Split>>> = self.splits.lock().unwrap();
+        let mut dividends: MutexGuard<HashMap<String, BTreeMap<NaiveDate, Dividend>>> = self.dividends.lock().unwrap();
+
+        if !stocks.contains_key(ticker) {
+            return Ok(false);
+        }
+
+        let records: usize = historical_data.get(ticker).map_or(0, TickerHistory::len)
+            + splits.get(ticker).map_or(0, BTreeMap::len)
+            + dividends.get(ticker).map_or(0, BTreeMap::len);
+        if records > 0 && !cascade {
+            return Err(StorageError::HasHistory(ticker.to_string(), records));
+        }
+
+        historical_data.remove(ticker);
+        splits.remove(ticker);
+        dividends.remove(ticker);
+        stocks.remove(ticker);
+        Ok(true)
+    }
+
+    async fn get_historical_data(&self, ticker: &str, query: &HistoryQuery) -> StorageResult<Option<HistoryPage>> {
+        let historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        let Some(data_points) = historical_data.get(ticker) else {
+            return Ok(None);
+        };
+
+        let total: usize = date_bounds(inclusive_bound(query.from), inclusive_bound(query.to))
+            .map_or(0, |bounds| data_points.range(bounds).count());
+
+        let (lower, upper): (Bound<NaiveDate>, Bound<NaiveDate>) = match query.order {
+            SortOrder::Asc => (lower_bound(query.from, query.cursor), inclusive_bound(query.to)),
+            SortOrder::Desc => (inclusive_bound(query.from), upper_bound(query.to, query.cursor)),
+        };
+        let offset: usize = query.offset.unwrap_or(0);
+        // Take one extra data point to find out whether another page follows
+        let take: usize = query.limit.map_or(usize::MAX, |limit: usize| limit.saturating_add(1));
+
+        let mut data: Vec<HistoricalDataPoint> = match date_bounds(lower, upper) {
+            Some(bounds) => {
+                let range = data_points.range(bounds).map(|(_, dp)| dp);
+                match query.order {
+                    SortOrder::Asc => range.skip(offset).take(take).cloned().collect(),
+                    SortOrder::Desc => range.rev().skip(offset).take(take).cloned().collect(),
+                }
+            }
+            None => Vec::new(),
+        };
+
+        let has_more: bool = query.limit.is_some_and(|limit: usize| data.len() > limit);
+        if let Some(limit) = query.limit {
+            data.truncate(limit);
+        }
+
+        Ok(Some(HistoryPage { data, total, has_more }))
+    }
+
+    async fn get_latest_data_point(&self, ticker: &str) -> StorageResult<Option<HistoricalDataPoint>> {
+        let historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        Ok(historical_data.get(ticker)
+            .and_then(|data_points: &TickerHistory| data_points.last_key_value())
+            .map(|(_, dp)| dp.clone()))
+    }
+
+    async fn get_dates(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Option<Vec<NaiveDate>>> {
+        let historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        Ok(historical_data.get(ticker).map(|data_points: &TickerHistory| {
+            date_bounds(inclusive_bound(range.from), inclusive_bound(range.to))
+                .map_or_else(Vec::new, |bounds| data_points.range(bounds).map(|(date, _)| *date).collect())
+        }))
+    }
+
+    async fn insert_data_point(&self, ticker: &str, data_point: &HistoricalDataPoint) -> StorageResult<bool> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        if !stocks.contains_key(ticker) {
+            return Err(StorageError::StockNotFound(ticker.to_string()));
+        }
+
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+        let data_points: &mut TickerHistory = historical_data.entry(ticker.to_string()).or_default();
+
+        if data_points.contains_key(&data_point.date) {
+            return Ok(false);
+        }
+
+        data_points.insert(data_point.date, data_point.clone());
+        Ok(true)
+    }
+
+    async fn write_data_points(
+        &self,
+        ticker: &str,
+        batch: &[HistoricalDataPoint],
+        mode: BatchMode,
+    ) -> StorageResult<Vec<BatchItemResult>> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        if !stocks.contains_key(ticker) {
+            return Err(StorageError::StockNotFound(ticker.to_string()));
+        }
+
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+        let data_points: &mut TickerHistory = historical_data.entry(ticker.to_string()).or_default();
+        let mut seen_dates: HashSet<NaiveDate> = HashSet::new();
+        let mut results: Vec<BatchItemResult> = Vec::with_capacity(batch.len());
+
+        for data_point in batch {
+            if !seen_dates.insert(data_point.date) {
+                results.push(BatchItemResult {
+                    date: Some(data_point.date),
+                    status: BatchItemStatus::Invalid,
+                    reason: Some("duplicate date in batch".to_string()),
+                });
+                continue;
+            }
+
+            let status: BatchItemStatus = batch_item_status(data_points.get(&data_point.date), data_point, mode);
+
+            if matches!(status, BatchItemStatus::Created | BatchItemStatus::Updated) {
+                data_points.insert(data_point.date, data_point.clone());
+            }
+
+            results.push(BatchItemResult {
+                date: Some(data_point.date),
+                status,
+                reason: None,
+            });
+        }
+
+        // A batch that stored nothing must not leave an empty history behind
+        if data_points.is_empty() {
+            historical_data.remove(ticker);
+        }
+
+        Ok(results)
+    }
+
+    async fn get_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<HistoricalDataPoint>> {
+        let historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        Ok(historical_data.get(ticker)
+            .and_then(|data_points: &TickerHistory| data_points.get(&date))
+            .cloned())
+    }
+
+    async fn update_data_point(&self, ticker: &str, date: NaiveDate, data_point: &HistoricalDataPoint) -> StorageResult<bool> {
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        let Some(data_points) = historical_data.get_mut(ticker) else {
+            return Ok(false);
+        };
+
+        if !data_points.contains_key(&date) {
+            return Ok(false);
+        }
+
+        if data_point.date != date && data_points.contains_key(&data_point.date) {
+            return Err(StorageError::Conflict(format!("{} already has data for {}", ticker, data_point.date)));
+        }
+
+        data_points.remove(&date);
+        data_points.insert(data_point.date, data_point.clone());
+        Ok(true)
+    }
+
+    async fn delete_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        let mut historical_data: MutexGuard<HashMap<String, TickerHistory>> = self.historical_data.lock().unwrap();
+
+        let Some(data_points) = historical_data.get_mut(ticker) else {
+            return Ok(false);
+        };
+
+        let deleted: bool = data_points.remove(&date).is_some();
+        if data_points.is_empty() {
+            historical_data.remove(ticker);
+        }
+
+        Ok(deleted)
+    }
+
+    async fn get_splits(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Split>> {
+        Ok(actions_in_range(&self.splits, ticker, range))
+    }
+
+    async fn insert_split(&self, ticker: &str, split: &Split) -> StorageResult<bool> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        if !stocks.contains_key(ticker) {
+            return Err(StorageError::StockNotFound(ticker.to_string()));
+        }
+
+        Ok(insert_action(&self.splits, ticker, split.date, split))
+    }
+
+    async fn get_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Split>> {
+        Ok(get_action(&self.splits, ticker, date))
+    }
+
+    async fn update_split(&self, ticker: &str, date: NaiveDate, split: &Split) -> StorageResult<bool> {
+        update_action(&self.splits, ticker, date, split.date, split)
+    }
+
+    async fn delete_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        Ok(delete_action(&self.splits, ticker, date))
+    }
+
+    async fn get_dividends(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Dividend>> {
+        Ok(actions_in_range(&self.dividends, ticker, range))
+    }
+
+    async fn insert_dividend(&self, ticker: &str, dividend: &Dividend) -> StorageResult<bool> {
+        let stocks: MutexGuard<HashMap<String, Stock>> = self.stocks.lock().unwrap();
+        if !stocks.contains_key(ticker) {
+            return Err(StorageError::StockNotFound(ticker.to_string()));
+        }
+
+        Ok(insert_action(&self.dividends, ticker, dividend.date, dividend))
+    }
+
+    async fn get_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Dividend>> {
+        Ok(get_action(&self.dividends, ticker, date))
+    }
+
+    async fn update_dividend(&self, ticker: &str, date: NaiveDate, dividend: &Dividend) -> StorageResult<bool> {
+        update_action(&self.dividends, ticker, date, dividend.date, dividend)
+    }
+
+    async fn delete_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        Ok(delete_action(&self.dividends, ticker, date))
+    }
+}
@@ -0,0 +1,148 @@
+pub mod memory;
+pub mod sqlite;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use crate::models::{Stock, HistoricalDataPoint, HistoryQuery, DateRangeQuery, BatchMode, BatchItemStatus, BatchItemResult, Split, Dividend};
+
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("conflict: {0}")]
+    Conflict(String),
+    #[error("stock {0} not found")]
+    StockNotFound(String),
+    /// The stock still has this many data points, splits and dividends.
+    #[error("stock {0} still has {1} historical data points, splits or dividends")]
+    HasHistory(String, usize),
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("storage task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// A page of historical data, sorted by date.
+pub struct HistoryPage {
+    pub data: Vec<HistoricalDataPoint>,
+    /// Number of data points within the queried date range, ignoring the
+    /// cursor, offset and limit.
+    pub total: usize,
+    /// Whether more data points follow this page.
+    pub has_more: bool,
+}
+
+/// Decides what a batch write does with `data_point` given the point already
+/// stored for the same date, if any.
+pub fn batch_item_status(
+    existing: Option<&HistoricalDataPoint>,
+    data_point: &HistoricalDataPoint,
+    mode: BatchMode,
+) -> BatchItemStatus {
+    match (existing, mode) {
+        (None, _) => BatchItemStatus::Created,
+        (Some(existing), _) if existing == data_point => BatchItemStatus::Unchanged,
+        (Some(_), BatchMode::Insert) => BatchItemStatus::Conflicted,
+        (Some(_), BatchMode::Upsert) => BatchItemStatus::Updated,
+    }
+}
+
+/// Backend holding stocks and their historical data.
+///
+/// Tickers are passed through as-is; handlers are responsible for
+/// normalizing them before calling into the storage.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn get_stocks(&self) -> StorageResult<Vec<Stock>>;
+
+    async fn get_stock(&self, ticker: &str) -> StorageResult<Option<Stock>>;
+
+    /// Inserts the stock, replacing any existing stock with the same ticker.
+    async fn upsert_stock(&self, stock: &Stock) -> StorageResult<()>;
+
+    /// Updates the stock stored under `ticker`. Returns `false` if there is no such stock.
+    ///
+    /// If `stock.ticker` differs from `ticker`, the stock, its whole history
+    /// and its splits and dividends are atomically moved to the new ticker,
+    /// which must not be in use yet.
+    async fn update_stock(&self, ticker: &str, stock: &Stock) -> StorageResult<bool>;
+
+    /// Deletes the stock stored under `ticker`. Returns `false` if there is no such stock.
+    ///
+    /// A stock with historical data, splits or dividends is only deleted,
+    /// along with them, if `cascade` is set; otherwise
+    /// [`StorageError::HasHistory`] is returned.
+    async fn delete_stock(&self, ticker: &str, cascade: bool) -> StorageResult<bool>;
+
+    /// Returns the page of the history of `ticker` selected by `query`, or
+    /// `None` if the ticker has no history at all.
+    async fn get_historical_data(&self, ticker: &str, query: &HistoryQuery) -> StorageResult<Option<HistoryPage>>;
+
+    /// Returns the data point with the most recent date, or `None` if the ticker has no history.
+    async fn get_latest_data_point(&self, ticker: &str) -> StorageResult<Option<HistoricalDataPoint>>;
+
+    /// Returns the sorted dates of the data points of `ticker` within `range`,
+    /// or `None` if the ticker has no history at all.
+    async fn get_dates(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Option<Vec<NaiveDate>>>;
+
+    /// Inserts the data point. Returns `false`, leaving the stored point
+    /// untouched, if `ticker` already has a data point for that date, and
+    /// [`StorageError::StockNotFound`] if there is no stock with that ticker.
+    async fn insert_data_point(&self, ticker: &str, data_point: &HistoricalDataPoint) -> StorageResult<bool>;
+
+    /// Writes all data points in a single transaction according to `mode`,
+    /// returning one result per data point in the same order. A date repeated
+    /// within the batch is reported as invalid after its first occurrence.
+    /// Fails with [`StorageError::StockNotFound`] if there is no stock with that ticker.
+    async fn write_data_points(
+        &self,
+        ticker: &str,
+        data_points: &[HistoricalDataPoint],
+        mode: BatchMode,
+    ) -> StorageResult<Vec<BatchItemResult>>;
+
+    async fn get_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<HistoricalDataPoint>>;
+
+    /// Replaces the data point stored for `date`. Returns `false` if there is
+    /// no such point, and a conflict if the replacement moves it onto a date
+    /// that already has one.
+    async fn update_data_point(&self, ticker: &str, date: NaiveDate, data_point: &HistoricalDataPoint) -> StorageResult<bool>;
+
+    /// Deletes the data point stored for `date`. Returns `false` if there is no such point.
+    async fn delete_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool>;
+
+    /// Returns the splits of `ticker` within `range`, oldest first.
+    async fn get_splits(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Split>>;
+
+    /// Inserts the split. Returns `false`, leaving the stored split untouched,
+    /// if `ticker` already has a split on that date, and
+    /// [`StorageError::StockNotFound`] if there is no stock with that ticker.
+    async fn insert_split(&self, ticker: &str, split: &Split) -> StorageResult<bool>;
+
+    async fn get_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Split>>;
+
+    /// Replaces the split stored for `date`. Returns `false` if there is no
+    /// such split, and a conflict if the replacement moves it onto a date
+    /// that already has one.
+    async fn update_split(&self, ticker: &str, date: NaiveDate, split: &Split) -> StorageResult<bool>;
+
+    /// Deletes the split stored for `date`. Returns `false` if there is no such split.
+    async fn delete_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool>;
+
+    /// Returns the dividends of `ticker` within `range`, oldest first.
+    async fn get_dividends(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Dividend>>;
+
+    /// Inserts the dividend, with the same semantics as [`Storage::insert_split`].
+    async fn insert_dividend(&self, ticker: &str, dividend: &Dividend) -> StorageResult<bool>;
+
+    async fn get_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Dividend>>;
+
+    /// Replaces the dividend stored for `date`, with the same semantics as [`Storage::update_split`].
+    async fn update_dividend(&self, ticker: &str, date: NaiveDate, dividend: &Dividend) -> StorageResult<bool>;
+
+    /// Deletes the dividend stored for `date`. Returns `false` if there is no such dividend.
+    async fn delete_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool>;
+}
@@ -0,0 +1,738 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row, ToSql};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use crate::models::{Stock, AssetClass, HistoricalDataPoint, HistoryQuery, SortOrder, DateRangeQuery, BatchMode, BatchItemStatus, BatchItemResult, Split, Dividend};
+use super::{batch_item_status, HistoryPage, Storage, StorageError, StorageResult};
+
+/// Schema migrations, applied in order. The index of the last applied
+/// migration is tracked in SQLite's `user_version` pragma, so new
+/// migrations must only ever be appended to this list.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE stocks (
+        ticker TEXT PRIMARY KEY NOT NULL,
+        stock_exchange TEXT NOT NULL
+    );
+    CREATE TABLE historical_data (
+        ticker TEXT NOT NULL,
+        date TEXT NOT NULL,
+        open REAL NOT NULL,
+        high REAL NOT NULL,
+        low REAL NOT NULL,
+        close REAL NOT NULL,
+        volume INTEGER NOT NULL,
+        PRIMARY KEY (ticker, date)
+    );",
+    "ALTER TABLE stocks ADD COLUMN name TEXT;
+    ALTER TABLE stocks ADD COLUMN currency TEXT;
+    ALTER TABLE stocks ADD COLUMN asset_class TEXT;
+    ALTER TABLE stocks ADD COLUMN sector TEXT;
+    ALTER TABLE stocks ADD COLUMN industry TEXT;
+    ALTER TABLE stocks ADD COLUMN isin TEXT;
+    ALTER TABLE stocks ADD COLUMN figi TEXT;
+    ALTER TABLE stocks ADD COLUMN listed_on TEXT;
+    ALTER TABLE stocks ADD COLUMN delisted_on TEXT;",
+    "CREATE TABLE splits (
+        ticker TEXT NOT NULL,
+        date TEXT NOT NULL,
+        numerator REAL NOT NULL,
+        denominator REAL NOT NULL,
+        PRIMARY KEY (ticker, date)
+    );
+    CREATE TABLE dividends (
+        ticker TEXT NOT NULL,
+        date TEXT NOT NULL,
+        amount REAL NOT NULL,
+        PRIMARY KEY (ticker, date)
+    );",
+];
+
+/// Tables holding rows keyed by ticker besides `stocks`, which move along
+/// when a stock is renamed and go when it is deleted.
+const TICKER_TABLES: &[&str] = &["historical_data", "splits", "dividends"];
+
+const STOCK_COLUMNS: &str =
+    "ticker, stock_exchange, name, currency, asset_class, sector, industry, isin, figi, listed_on, delisted_on";
+
+/// Storage persisted to a SQLite database file.
+///
+/// Optional date bounds are bound through `COALESCE` with sentinels rather
+/// than `?n IS NULL OR ...` so that range queries can use the primary key index.
+///
+/// rusqlite is blocking, so every call runs on Tokio's blocking thread pool
+/// rather than on the thread serving the request.
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let mut conn: Connection = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row: &Row| row.get(0))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `f` with the connection on the blocking thread pool.
+    async fn run<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> StorageResult<T> + Send + 'static,
+    {
+        let conn: Arc<Mutex<Connection>> = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn: MutexGuard<Connection> = conn.lock().unwrap();
+            f(&mut conn)
+        })
+        .await?
+    }
+
+    /// Deletes the row of `table` for `ticker` on `date`, returning whether there was one.
+    async fn delete_dated(&self, table: &'static str, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            let deleted: usize = conn.execute(
+                &format!("DELETE FROM {} WHERE ticker = ?1 AND date = ?2", table),
+                params![ticker, date],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn get_stocks(&self) -> StorageResult<Vec<Stock>> {
+        self.run(|conn: &mut Connection| {
+            let mut statement = conn.prepare(&format!("SELECT {} FROM stocks ORDER BY ticker", STOCK_COLUMNS))?;
+            let stocks = statement.query_map([], stock_from_row)?
+                .collect::<rusqlite::Result<Vec<Stock>>>()?;
+            Ok(stocks)
+        })
+        .await
+    }
+
+    async fn get_stock(&self, ticker: &str) -> StorageResult<Option<Stock>> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            conn.query_row(
+                &format!("SELECT {} FROM stocks WHERE ticker = ?1", STOCK_COLUMNS),
+                params![ticker],
+                stock_from_row,
+            )
+            .optional()
+            .map_err(StorageError::from)
+        })
+        .await
+    }
+
+    async fn upsert_stock(&self, stock: &Stock) -> StorageResult<()> {
+        let stock: Stock = stock.clone();
+
+        self.run(move |conn: &mut Connection| {
+            conn.execute(
+                &format!(
+                    "INSERT INTO stocks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                     ON CONFLICT (ticker) DO UPDATE SET
+                        stock_exchange = excluded.stock_exchange, name = excluded.name,
+                        currency = excluded.currency, asset_class = excluded.asset_class,
+                        sector = excluded.sector, industry = excluded.industry,
+                        isin = excluded.isin, figi = excluded.figi,
+                        listed_on = excluded.listed_on, delisted_on = excluded.delisted_on",
+                    STOCK_COLUMNS
+                ),
+                stock_params(&stock),
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_stock(&self, ticker: &str, stock: &Stock) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let stock: Stock = stock.clone();
+
+        self.run(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+
+            if !stock_exists(&tx, &ticker)? {
+                return Ok(false);
+            }
+
+            if stock.ticker != ticker {
+                let target_taken: bool = stock_exists(&tx, &stock.ticker)? || history_count(&tx, &stock.ticker)? > 0;
+                if target_taken {
+                    return Err(StorageError::Conflict(format!("ticker {} is already in use", stock.ticker)));
+                }
+
+                for table in TICKER_TABLES {
+                    tx.execute(
+                        &format!("UPDATE {} SET ticker = ?2 WHERE ticker = ?1", table),
+                        params![ticker, stock.ticker],
+                    )?;
+                }
+            }
+
+            tx.execute("DELETE FROM stocks WHERE ticker = ?1", params![ticker])?;
+            tx.execute(
+                &format!("INSERT INTO stocks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)", STOCK_COLUMNS),
+                stock_params(&stock),
+            )?;
+
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn delete_stock(&self, ticker: &str, cascade: bool) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+
+            if !stock_exists(&tx, &ticker)? {
+                return Ok(false);
+            }
+
+            let records: usize = TICKER_TABLES.iter()
+                .map(|table: &&str| ticker_count(&tx, table, &ticker))
+                .sum::<rusqlite::Result<usize>>()?;
+            if records > 0 && !cascade {
+                return Err(StorageError::HasHistory(ticker, records));
+            }
+
+            for table in TICKER_TABLES {
+                tx.execute(&format!("DELETE FROM {} WHERE ticker = ?1", table), params![ticker])?;
+            }
+            tx.execute("DELETE FROM stocks WHERE ticker = ?1", params![ticker])?;
+
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn get_historical_data(&self, ticker: &str, query: &HistoryQuery) -> StorageResult<Option<HistoryPage>> {
+        let ticker: String = ticker.to_string();
+        let query: HistoryQuery = query.clone();
+
+        self.run(move |conn: &mut Connection| {
+            let has_history: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM historical_data WHERE ticker = ?1)",
+                params![ticker],
+                |row: &Row| row.get(0),
+            )?;
+            if !has_history {
+                return Ok(None);
+            }
+
+            let total: usize = conn.query_row(
+                "SELECT COUNT(*) FROM historical_data
+                 WHERE ticker = ?1 AND date >= COALESCE(?2, '') AND date <= COALESCE(?3, '9999-12-31')",
+                params![ticker, query.from, query.to],
+                |row: &Row| row.get(0),
+            )?;
+
+            let (cursor_condition, direction): (&str, &str) = match query.order {
+                SortOrder::Asc => ("date > COALESCE(?4, '')", "ASC"),
+                SortOrder::Desc => ("date < COALESCE(?4, '9999-12-32')", "DESC"),
+            };
+            let mut statement = conn.prepare(&format!(
+                "SELECT date, open, high, low, close, volume FROM historical_data
+                 WHERE ticker = ?1 AND date >= COALESCE(?2, '') AND date <= COALESCE(?3, '9999-12-31')
+                   AND {}
+                 ORDER BY date {} LIMIT ?5 OFFSET ?6",
+                cursor_condition, direction
+            ))?;
+
+            // Fetch one extra row to find out whether another page follows
+            let limit: Option<usize> = query.limit;
+            let sql_limit: i64 = limit.map_or(-1, |limit: usize| i64::try_from(limit).unwrap_or(i64::MAX).saturating_add(1));
+            let offset: i64 = i64::try_from(query.offset.unwrap_or(0)).unwrap_or(i64::MAX);
+
+            let mut data: Vec<HistoricalDataPoint> = statement
+                .query_map(
+                    params![ticker, query.from, query.to, query.cursor, sql_limit, offset],
+                    data_point_from_row,
+                )?
+                .collect::<rusqlite::Result<Vec<HistoricalDataPoint>>>()?;
+
+            let has_more: bool = limit.is_some_and(|limit: usize| data.len() > limit);
+            if let Some(limit) = limit {
+                data.truncate(limit);
+            }
+
+            Ok(Some(HistoryPage { data, total, has_more }))
+        })
+        .await
+    }
+
+    async fn get_latest_data_point(&self, ticker: &str) -> StorageResult<Option<HistoricalDataPoint>> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT date, open, high, low, close, volume FROM historical_data
+                 WHERE ticker = ?1 ORDER BY date DESC LIMIT 1",
+                params![ticker],
+                data_point_from_row,
+            )
+            .optional()
+            .map_err(StorageError::from)
+        })
+        .await
+    }
+
+    async fn get_dates(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Option<Vec<NaiveDate>>> {
+        let ticker: String = ticker.to_string();
+        let (from, to): (Option<NaiveDate>, Option<NaiveDate>) = (range.from, range.to);
+
+        self.run(move |conn: &mut Connection| {
+            let has_history: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM historical_data WHERE ticker = ?1)",
+                params![ticker],
+                |row: &Row| row.get(0),
+            )?;
+            if !has_history {
+                return Ok(None);
+            }
+
+            let mut statement = conn.prepare(
+                "SELECT date FROM historical_data
+                 WHERE ticker = ?1 AND date >= COALESCE(?2, '') AND date <= COALESCE(?3, '9999-12-31')
+                 ORDER BY date",
+            )?;
+            let dates: Vec<NaiveDate> = statement
+                .query_map(params![ticker, from, to], |row: &Row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<NaiveDate>>>()?;
+
+            Ok(Some(dates))
+        })
+        .await
+    }
+
+    async fn insert_data_point(&self, ticker: &str, data_point: &HistoricalDataPoint) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let data_point: HistoricalDataPoint = data_point.clone();
+
+        self.run(move |conn: &mut Connection| {
+            if !stock_exists(conn, &ticker)? {
+                return Err(StorageError::StockNotFound(ticker));
+            }
+
+            let inserted: usize = conn.execute(
+                "INSERT INTO historical_data (ticker, date, open, high, low, close, volume)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT (ticker, date) DO NOTHING",
+                params![
+                    ticker,
+                    data_point.date,
+                    data_point.open,
+                    data_point.high,
+                    data_point.low,
+                    data_point.close,
+                    data_point.volume,
+                ],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn write_data_points(
+        &self,
+        ticker: &str,
+        batch: &[HistoricalDataPoint],
+        mode: BatchMode,
+    ) -> StorageResult<Vec<BatchItemResult>> {
+        let ticker: String = ticker.to_string();
+        let batch: Vec<HistoricalDataPoint> = batch.to_vec();
+
+        self.run(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+
+            if !stock_exists(&tx, &ticker)? {
+                return Err(StorageError::StockNotFound(ticker));
+            }
+
+            let mut seen_dates: HashSet<NaiveDate> = HashSet::new();
+            let mut results: Vec<BatchItemResult> = Vec::with_capacity(batch.len());
+
+            {
+                let mut select = tx.prepare_cached(
+                    "SELECT date, open, high, low, close, volume FROM historical_data
+                     WHERE ticker = ?1 AND date = ?2",
+                )?;
+                let mut write = tx.prepare_cached(
+                    "INSERT INTO historical_data (ticker, date, open, high, low, close, volume)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT (ticker, date) DO UPDATE SET
+                        open = excluded.open, high = excluded.high, low = excluded.low,
+                        close = excluded.close, volume = excluded.volume",
+                )?;
+
+                for data_point in &batch {
+                    if !seen_dates.insert(data_point.date) {
+                        results.push(BatchItemResult {
+                            date: Some(data_point.date),
+                            status: BatchItemStatus::Invalid,
+                            reason: Some("duplicate date in batch".to_string()),
+                        });
+                        continue;
+                    }
+
+                    let existing: Option<HistoricalDataPoint> = select
+                        .query_row(params![ticker, data_point.date], data_point_from_row)
+                        .optional()?;
+                    let status: BatchItemStatus = batch_item_status(existing.as_ref(), data_point, mode);
+
+                    if matches!(status, BatchItemStatus::Created | BatchItemStatus::Updated) {
+                        write.execute(params![
+                            ticker,
+                            data_point.date,
+                            data_point.open,
+                            data_point.high,
+                            data_point.low,
+                            data_point.close,
+                            data_point.volume,
+                        ])?;
+                    }
+
+                    results.push(BatchItemResult {
+                        date: Some(data_point.date),
+                        status,
+                        reason: None,
+                    });
+                }
+            }
+
+            tx.commit()?;
+            Ok(results)
+        })
+        .await
+    }
+
+    async fn get_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<HistoricalDataPoint>> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT date, open, high, low, close, volume FROM historical_data
+                 WHERE ticker = ?1 AND date = ?2",
+                params![ticker, date],
+                data_point_from_row,
+            )
+            .optional()
+            .map_err(StorageError::from)
+        })
+        .await
+    }
+
+    async fn update_data_point(&self, ticker: &str, date: NaiveDate, data_point: &HistoricalDataPoint) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let data_point: HistoricalDataPoint = data_point.clone();
+
+        self.run(move |conn: &mut Connection| {
+            let result: rusqlite::Result<usize> = conn.execute(
+                "UPDATE historical_data
+                 SET date = ?3, open = ?4, high = ?5, low = ?6, close = ?7, volume = ?8
+                 WHERE ticker = ?1 AND date = ?2",
+                params![
+                    ticker,
+                    date,
+                    data_point.date,
+                    data_point.open,
+                    data_point.high,
+                    data_point.low,
+                    data_point.close,
+                    data_point.volume,
+                ],
+            );
+
+            match result {
+                Ok(updated) => Ok(updated > 0),
+                Err(e) if e.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => {
+                    Err(StorageError::Conflict(format!("{} already has data for {}", ticker, data_point.date)))
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+        .await
+    }
+
+    async fn delete_data_point(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        self.delete_dated("historical_data", ticker, date).await
+    }
+
+    async fn get_splits(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Split>> {
+        let ticker: String = ticker.to_string();
+        let (from, to): (Option<NaiveDate>, Option<NaiveDate>) = (range.from, range.to);
+
+        self.run(move |conn: &mut Connection| {
+            let mut statement = conn.prepare(
+                "SELECT date, numerator, denominator FROM splits
+                 WHERE ticker = ?1 AND date >= COALESCE(?2, '') AND date <= COALESCE(?3, '9999-12-31')
+                 ORDER BY date",
+            )?;
+            let splits = statement.query_map(params![ticker, from, to], split_from_row)?
+                .collect::<rusqlite::Result<Vec<Split>>>()?;
+            Ok(splits)
+        })
+        .await
+    }
+
+    async fn insert_split(&self, ticker: &str, split: &Split) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let split: Split = split.clone();
+
+        self.run(move |conn: &mut Connection| {
+            if !stock_exists(conn, &ticker)? {
+                return Err(StorageError::StockNotFound(ticker));
+            }
+
+            let inserted: usize = conn.execute(
+                "INSERT INTO splits (ticker, date, numerator, denominator) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (ticker, date) DO NOTHING",
+                params![ticker, split.date, split.numerator, split.denominator],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn get_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Split>> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT date, numerator, denominator FROM splits WHERE ticker = ?1 AND date = ?2",
+                params![ticker, date],
+                split_from_row,
+            )
+            .optional()
+            .map_err(StorageError::from)
+        })
+        .await
+    }
+
+    async fn update_split(&self, ticker: &str, date: NaiveDate, split: &Split) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let split: Split = split.clone();
+
+        self.run(move |conn: &mut Connection| {
+            let result: rusqlite::Result<usize> = conn.execute(
+                "UPDATE splits SET date = ?3, numerator = ?4, denominator = ?5 WHERE ticker = ?1 AND date = ?2",
+                params![ticker, date, split.date, split.numerator, split.denominator],
+            );
+
+            corporate_action_updated(result, &ticker, split.date)
+        })
+        .await
+    }
+
+    async fn delete_split(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        self.delete_dated("splits", ticker, date).await
+    }
+
+    async fn get_dividends(&self, ticker: &str, range: &DateRangeQuery) -> StorageResult<Vec<Dividend>> {
+        let ticker: String = ticker.to_string();
+        let (from, to): (Option<NaiveDate>, Option<NaiveDate>) = (range.from, range.to);
+
+        self.run(move |conn: &mut Connection| {
+            let mut statement = conn.prepare(
+                "SELECT date, amount FROM dividends
+                 WHERE ticker = ?1 AND date >= COALESCE(?2, '') AND date <= COALESCE(?3, '9999-12-31')
+                 ORDER BY date",
+            )?;
+            let dividends = statement.query_map(params![ticker, from, to], dividend_from_row)?
+                .collect::<rusqlite::Result<Vec<Dividend>>>()?;
+            Ok(dividends)
+        })
+        .await
+    }
+
+    async fn insert_dividend(&self, ticker: &str, dividend: &Dividend) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let dividend: Dividend = dividend.clone();
+
+        self.run(move |conn: &mut Connection| {
+            if !stock_exists(conn, &ticker)? {
+                return Err(StorageError::StockNotFound(ticker));
+            }
+
+            let inserted: usize = conn.execute(
+                "INSERT INTO dividends (ticker, date, amount) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (ticker, date) DO NOTHING",
+                params![ticker, dividend.date, dividend.amount],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn get_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<Option<Dividend>> {
+        let ticker: String = ticker.to_string();
+
+        self.run(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT date, amount FROM dividends WHERE ticker = ?1 AND date = ?2",
+                params![ticker, date],
+                dividend_from_row,
+            )
+            .optional()
+            .map_err(StorageError::from)
+        })
+        .await
+    }
+
+    async fn update_dividend(&self, ticker: &str, date: NaiveDate, dividend: &Dividend) -> StorageResult<bool> {
+        let ticker: String = ticker.to_string();
+        let dividend: Dividend = dividend.clone();
+
+        self.run(move |conn: &mut Connection| {
+            let result: rusqlite::Result<usize> = conn.execute(
+                "UPDATE dividends SET date = ?3, amount = ?4 WHERE ticker = ?1 AND date = ?2",
+                params![ticker, date, dividend.date, dividend.amount],
+            );
+
+            corporate_action_updated(result, &ticker, dividend.date)
+        })
+        .await
+    }
+
+    async fn delete_dividend(&self, ticker: &str, date: NaiveDate) -> StorageResult<bool> {
+        self.delete_dated("dividends", ticker, date).await
+    }
+}
+
+/// Turns the result of updating a corporate action into whether it existed,
+/// reporting a move onto a date that already has one as a conflict.
+fn corporate_action_updated(result: rusqlite::Result<usize>, ticker: &str, new_date: NaiveDate) -> StorageResult<bool> {
+    match result {
+        Ok(updated) => Ok(updated > 0),
+        Err(e) if e.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => {
+            Err(StorageError::Conflict(format!("{} already has a corporate action on {}", ticker, new_date)))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn stock_exists(conn: &Connection, ticker: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM stocks WHERE ticker = ?1)",
+        params![ticker],
+        |row: &Row| row.get(0),
+    )
+}
+
+fn history_count(conn: &Connection, ticker: &str) -> rusqlite::Result<usize> {
+    ticker_count(conn, "historical_data", ticker)
+}
+
+/// Number of rows of `table` belonging to `ticker`.
+fn ticker_count(conn: &Connection, table: &str, ticker: &str) -> rusqlite::Result<usize> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE ticker = ?1", table),
+        params![ticker],
+        |row: &Row| row.get(0),
+    )
+}
+
+/// Binds a stock to parameters `?1` to `?11`, in the order of [`STOCK_COLUMNS`].
+fn stock_params(stock: &Stock) -> [&dyn ToSql; 11] {
+    [
+        &stock.ticker,
+        &stock.stock_exchange,
+        &stock.name,
+        &stock.currency,
+        &stock.asset_class,
+        &stock.sector,
+        &stock.industry,
+        &stock.isin,
+        &stock.figi,
+        &stock.listed_on,
+        &stock.delisted_on,
+    ]
+}
+
+/// Reads a stock from a row selecting [`STOCK_COLUMNS`].
+fn stock_from_row(row: &Row) -> rusqlite::Result<Stock> {
+    Ok(Stock {
+        ticker: row.get(0)?,
+        stock_exchange: row.get(1)?,
+        name: row.get(2)?,
+        currency: row.get(3)?,
+        asset_class: row.get(4)?,
+        sector: row.get(5)?,
+        industry: row.get(6)?,
+        isin: row.get(7)?,
+        figi: row.get(8)?,
+        listed_on: row.get(9)?,
+        delisted_on: row.get(10)?,
+    })
+}
+
+impl ToSql for AssetClass {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for AssetClass {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+fn data_point_from_row(row: &Row) -> rusqlite::Result<HistoricalDataPoint> {
+    Ok(HistoricalDataPoint {
+        date: row.get(0)?,
+        open: row.get(1)?,
+        high: row.get(2)?,
+        low: row.get(3)?,
+        close: row.get(4)?,
+        volume: row.get(5)?,
+    })
+}
+
+fn split_from_row(row: &Row) -> rusqlite::Result<Split> {
+    Ok(Split {
+        date: row.get(0)?,
+        numerator: row.get(1)?,
+        denominator: row.get(2)?,
+    })
+}
+
+fn dividend_from_row(row: &Row) -> rusqlite::Result<Dividend> {
+    Ok(Dividend {
+        date: row.get(0)?,
+        amount: row.get(1)?,
+    })
+}
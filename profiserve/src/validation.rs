@@ -0,0 +1,224 @@
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::error::ApiError;
+use crate::models::{Dividend, HistoricalDataPoint, HistoryQuery, Split, Stock};
+
+/// Describes which field of a request body is invalid and why.
+#[derive(Serialize, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), FieldError>;
+}
+
+impl Validate for HistoricalDataPoint {
+    fn validate(&self) -> Result<(), FieldError> {
+        validate_date(self.date)?;
+
+        let prices: [(&str, f64); 4] = [
+            ("open", self.open),
+            ("high", self.high),
+            ("low", self.low),
+            ("close", self.close),
+        ];
+        for (field, price) in prices {
+            if !price.is_finite() || price < 0.0 {
+                return Err(FieldError::new(field, "must be a finite, non-negative number"));
+            }
+        }
+
+        if self.low > self.high {
+            return Err(FieldError::new("low", "must not be greater than high"));
+        }
+        if self.open < self.low || self.open > self.high {
+            return Err(FieldError::new("open", "must be between low and high"));
+        }
+        if self.close < self.low || self.close > self.high {
+            return Err(FieldError::new("close", "must be between low and high"));
+        }
+
+        // SQLite stores integers as signed 64-bit values
+        if i64::try_from(self.volume).is_err() {
+            return Err(FieldError::new("volume", format!("must be at most {}", i64::MAX)));
+        }
+
+        Ok(())
+    }
+}
+
+impl Validate for Split {
+    fn validate(&self) -> Result<(), FieldError> {
+        validate_date(self.date)?;
+
+        let ratio: [(&str, f64); 2] = [
+            ("numerator", self.numerator),
+            ("denominator", self.denominator),
+        ];
+        for (field, value) in ratio {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(FieldError::new(field, "must be a finite, positive number"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Validate for Dividend {
+    fn validate(&self) -> Result<(), FieldError> {
+        validate_date(self.date)?;
+
+        if !self.amount.is_finite() || self.amount <= 0.0 {
+            return Err(FieldError::new("amount", "must be a finite, positive number"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Validate for HistoryQuery {
+    fn validate(&self) -> Result<(), FieldError> {
+        if self.limit == Some(0) {
+            return Err(FieldError::new("limit", "must be at least 1"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Validate for Stock {
+    fn validate(&self) -> Result<(), FieldError> {
+        if self.ticker.trim().is_empty() {
+            return Err(FieldError::new("ticker", "must not be empty"));
+        }
+
+        if let Some(currency) = &self.currency {
+            if currency.len() != 3 || !currency.chars().all(|c: char| c.is_ascii_alphabetic()) {
+                return Err(FieldError::new("currency", "must be a three-letter ISO 4217 code"));
+            }
+        }
+
+        if let Some(isin) = &self.isin {
+            if !is_valid_isin(isin) {
+                return Err(FieldError::new("isin", "must be a valid 12-character ISIN"));
+            }
+        }
+
+        if let Some(figi) = &self.figi {
+            if figi.len() != 12 || !figi.chars().all(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+                return Err(FieldError::new("figi", "must be 12 uppercase letters or digits"));
+            }
+        }
+
+        if let (Some(listed_on), Some(delisted_on)) = (self.listed_on, self.delisted_on) {
+            if delisted_on < listed_on {
+                return Err(FieldError::new("delisted_on", "must not be before listed_on"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects dates past year 9999, which the storage could not keep in date
+/// order since it compares dates as `YYYY-MM-DD` text.
+fn validate_date(date: NaiveDate) -> Result<(), FieldError> {
+    let last_date: NaiveDate = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+    if date > last_date {
+        return Err(FieldError::new("date", "must not be after 9999-12-31"));
+    }
+
+    Ok(())
+}
+
+/// Checks the format and the Luhn check digit of an ISIN.
+fn is_valid_isin(isin: &str) -> bool {
+    let bytes: &[u8] = isin.as_bytes();
+    let well_formed: bool = bytes.len() == 12
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..11].iter().all(|b: &u8| b.is_ascii_uppercase() || b.is_ascii_digit())
+        && bytes[11].is_ascii_digit();
+    if !well_formed {
+        return false;
+    }
+
+    // Letters count as two digits (A = 10 ... Z = 35), then the Luhn
+    // algorithm runs over the resulting digits
+    let digits: Vec<u32> = isin.chars()
+        .flat_map(|c: char| {
+            let value: u32 = c.to_digit(36).unwrap_or(0);
+            if value >= 10 { vec![value / 10, value % 10] } else { vec![value] }
+        })
+        .collect();
+
+    let sum: u32 = digits.iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit): (usize, &u32)| {
+            if i % 2 == 1 {
+                let doubled: u32 = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                *digit
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// JSON body extractor that reports the path of the field that failed to
+/// deserialize, then runs the body's own validation.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body: Bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| ApiError::bad_request(rejection.body_text()))?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let inner: &serde_json::Error = e.inner();
+
+            if inner.is_syntax() || inner.is_eof() {
+                ApiError::bad_request(format!("malformed JSON body: {}", inner))
+            } else {
+                ApiError::from(FieldError::new(&e.path().to_string(), inner.to_string()))
+            }
+        })?;
+
+        value.validate()?;
+
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,621 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use http_body_util::BodyExt;
+use profiserve::routes::create_router;
+use profiserve::state::AppState;
+use profiserve::storage::{MemoryStorage, SqliteStorage};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    create_router(AppState::new(Arc::new(MemoryStorage::new())))
+}
+
+fn sqlite_app() -> Router {
+    create_router(AppState::new(Arc::new(SqliteStorage::open(":memory:").unwrap())))
+}
+
+/// Path of a database file of its own for a test, removed along with its
+/// WAL files by [`remove_db`].
+fn temp_db() -> PathBuf {
+    std::env::temp_dir().join(format!("profiserve-{}.db", uuid::Uuid::new_v4()))
+}
+
+fn remove_db(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        std::fs::remove_file(format!("{}{}", path.display(), suffix)).ok();
+    }
+}
+
+fn file_app(path: &Path) -> Router {
+    create_router(AppState::new(Arc::new(SqliteStorage::open(path.to_str().unwrap()).unwrap())))
+}
+
+fn request(method: Method, uri: &str, body: Option<&str>) -> Request<Body> {
+    let builder = Request::builder().method(method).uri(uri);
+
+    match body {
+        Some(body) => builder.header("content-type", "application/json").body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+/// Sends `request` to `app` and returns the status and the JSON body of the response.
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response: Response = app.clone().oneshot(request).await.unwrap();
+    let status: StatusCode = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn add_stock(app: &Router, ticker: &str) {
+    let body: String = json!({ "ticker": ticker, "stock_exchange": "NASDAQ" }).to_string();
+    let (status, _) = send(app, request(Method::POST, "/api/v1/stocks", Some(&body))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+async fn add_quote(app: &Router, ticker: &str, date: &str) {
+    let body: String = json!({ "date": date, "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0, "volume": 100 }).to_string();
+    let uri: String = format!("/api/v1/stocks/{}/history", ticker);
+    let (status, _) = send(app, request(Method::POST, &uri, Some(&body))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_bad_request() {
+    let app: Router = app();
+
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks", Some("{\"ticker\": "))).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+    assert!(body["message"].as_str().unwrap().starts_with("malformed JSON body"));
+}
+
+#[tokio::test]
+async fn invalid_path_parameters_are_bad_requests() {
+    let app: Router = app();
+    add_stock(&app, "AAPL").await;
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v1/stocks/AAPL/history/yesterday", None)).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn wrongly_typed_fields_are_unprocessable() {
+    let app: Router = app();
+    let stock: String = json!({ "ticker": "AAPL", "stock_exchange": 42 }).to_string();
+
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks", Some(&stock))).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"]["field"], "stock_exchange");
+}
+
+#[tokio::test]
+async fn invalid_values_are_unprocessable() {
+    let app: Router = app();
+    let stock: String = json!({ "ticker": "AAPL", "stock_exchange": "NASDAQ", "currency": "dollars" }).to_string();
+
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks", Some(&stock))).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["message"], "invalid currency");
+    assert_eq!(body["details"]["field"], "currency");
+}
+
+#[tokio::test]
+async fn currencies_keep_their_case() {
+    let app: Router = app();
+    let stock: String = json!({ "ticker": "vod.l", "stock_exchange": "LSE", "currency": "GBp" }).to_string();
+
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks", Some(&stock))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["ticker"], "VOD.L");
+    assert_eq!(body["currency"], "GBp");
+
+    let (_, body) = send(&app, request(Method::GET, "/api/v1/stocks/VOD.L", None)).await;
+    assert_eq!(body["currency"], "GBp");
+}
+
+#[tokio::test]
+async fn a_zero_limit_is_unprocessable() {
+    let app: Router = app();
+    add_stock(&app, "AAPL").await;
+    add_quote(&app, "AAPL", "2024-01-02").await;
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v1/stocks/AAPL/history?limit=0", None)).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["field"], "limit");
+}
+
+#[tokio::test]
+async fn values_sqlite_cannot_store_are_unprocessable() {
+    let app: Router = sqlite_app();
+    add_stock(&app, "AAPL").await;
+
+    let huge_volume: String = json!({ "date": "2024-01-02", "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0, "volume": u64::MAX }).to_string();
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/history", Some(&huge_volume))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["field"], "volume");
+
+    let far_bar: String = json!({ "date": "+10000-01-01", "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0, "volume": 100 }).to_string();
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/history", Some(&far_bar))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["field"], "date");
+    assert_eq!(body["details"]["message"], "must not be after 9999-12-31");
+
+    let far_dividend: String = json!({ "date": "+10000-01-01", "amount": 0.25 }).to_string();
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/dividends", Some(&far_dividend))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["details"]["field"], "date");
+
+    let last_day: String = json!({ "date": "9999-12-31", "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0, "volume": i64::MAX }).to_string();
+    let (status, _) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/history", Some(&last_day))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn huge_limits_are_accepted() {
+    let app: Router = sqlite_app();
+    add_stock(&app, "AAPL").await;
+    add_quote(&app, "AAPL", "2024-01-02").await;
+    let uri: String = format!("/api/v1/stocks/AAPL/history?limit={}", usize::MAX);
+
+    let (status, body) = send(&app, request(Method::GET, &uri, None)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["next_cursor"], Value::Null);
+}
+
+/// Dates of a page of history and its `next_cursor`, after checking its total.
+async fn history_page(app: &Router, query: &str, total: usize) -> (Vec<String>, Value) {
+    let uri: String = format!("/api/v1/stocks/AAPL/history?{}", query);
+    let (status, body) = send(app, request(Method::GET, &uri, None)).await;
+    assert_eq!(status, StatusCode::OK, "{}: {}", query, body);
+    assert_eq!(body["total"], total, "{}", query);
+
+    let dates: Vec<String> = body["data"].as_array().unwrap().iter()
+        .map(|data_point: &Value| data_point["date"].as_str().unwrap().to_string())
+        .collect();
+    (dates, body["next_cursor"].clone())
+}
+
+/// App of each backend holding AAPL's quotes from January 2nd to 8th, 2024.
+async fn paged_apps() -> Vec<Router> {
+    let mut apps: Vec<Router> = Vec::new();
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "AAPL").await;
+        for date in ["2024-01-02", "2024-01-03", "2024-01-04", "2024-01-05", "2024-01-08"] {
+            add_quote(&app, "AAPL", date).await;
+        }
+        apps.push(app);
+    }
+    apps
+}
+
+#[tokio::test]
+async fn history_is_filtered_by_date_range() {
+    for app in paged_apps().await {
+        let (dates, cursor) = history_page(&app, "from=2024-01-03&to=2024-01-05", 3).await;
+        assert_eq!(dates, ["2024-01-03", "2024-01-04", "2024-01-05"]);
+        assert_eq!(cursor, Value::Null);
+
+        let (dates, _) = history_page(&app, "to=2024-01-03", 2).await;
+        assert_eq!(dates, ["2024-01-02", "2024-01-03"]);
+        let (dates, _) = history_page(&app, "from=2024-01-06", 1).await;
+        assert_eq!(dates, ["2024-01-08"]);
+        let (dates, _) = history_page(&app, "from=2025-01-01", 0).await;
+        assert!(dates.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn history_pages_follow_the_cursor_in_descending_order() {
+    for app in paged_apps().await {
+        let (dates, cursor) = history_page(&app, "order=desc&limit=2", 5).await;
+        assert_eq!(dates, ["2024-01-08", "2024-01-05"]);
+        assert_eq!(cursor, "2024-01-05");
+
+        let (dates, cursor) = history_page(&app, "order=desc&limit=2&cursor=2024-01-05", 5).await;
+        assert_eq!(dates, ["2024-01-04", "2024-01-03"]);
+        assert_eq!(cursor, "2024-01-03");
+
+        let (dates, cursor) = history_page(&app, "order=desc&limit=2&cursor=2024-01-03", 5).await;
+        assert_eq!(dates, ["2024-01-02"]);
+        assert_eq!(cursor, Value::Null);
+    }
+}
+
+#[tokio::test]
+async fn history_pages_follow_the_cursor_within_a_range() {
+    for app in paged_apps().await {
+        let (dates, cursor) = history_page(&app, "from=2024-01-03&limit=2", 4).await;
+        assert_eq!(dates, ["2024-01-03", "2024-01-04"]);
+        assert_eq!(cursor, "2024-01-04");
+
+        // The last page is full, yet there is nothing after it
+        let (dates, cursor) = history_page(&app, "from=2024-01-03&limit=2&cursor=2024-01-04", 4).await;
+        assert_eq!(dates, ["2024-01-05", "2024-01-08"]);
+        assert_eq!(cursor, Value::Null);
+    }
+}
+
+#[tokio::test]
+async fn history_pages_skip_the_offset() {
+    for app in paged_apps().await {
+        let (dates, cursor) = history_page(&app, "limit=2&offset=1", 5).await;
+        assert_eq!(dates, ["2024-01-03", "2024-01-04"]);
+        assert_eq!(cursor, "2024-01-04");
+
+        let (dates, cursor) = history_page(&app, "order=desc&offset=3", 5).await;
+        assert_eq!(dates, ["2024-01-03", "2024-01-02"]);
+        assert_eq!(cursor, Value::Null);
+    }
+}
+
+/// A bar at `price` with a volume of 100, like the ones `add_quote` stores at 1.
+fn bar(date: &str, price: f64) -> Value {
+    json!({ "date": date, "open": price, "high": price, "low": price, "close": price, "volume": 100 })
+}
+
+/// Sends `items` as a batch in `mode` and returns the report, after checking
+/// the status of each item.
+async fn send_batch(app: &Router, mode: &str, items: Vec<Value>, statuses: &[&str]) -> Value {
+    let uri: String = format!("/api/v1/stocks/AAPL/history/batch?mode={}", mode);
+    let (status, body) = send(app, request(Method::POST, &uri, Some(&Value::Array(items).to_string()))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let results: Vec<&str> = body["results"].as_array().unwrap().iter()
+        .map(|result: &Value| result["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(results, statuses, "{}", body);
+    body
+}
+
+async fn close_on(app: &Router, date: &str) -> Value {
+    let uri: String = format!("/api/v1/stocks/AAPL/history/{}", date);
+    let (_, body) = send(app, request(Method::GET, &uri, None)).await;
+    body["close"].clone()
+}
+
+#[tokio::test]
+async fn insert_batches_leave_stored_bars_untouched() {
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "AAPL").await;
+        add_quote(&app, "AAPL", "2024-01-02").await;
+        add_quote(&app, "AAPL", "2024-01-03").await;
+
+        let report: Value = send_batch(&app, "insert", vec![
+            bar("2024-01-02", 1.0),
+            bar("2024-01-03", 2.0),
+            json!({ "date": "2024-01-04", "open": 1.0, "high": 0.5, "low": 1.0, "close": 1.0, "volume": 100 }),
+            bar("2024-01-05", 3.0),
+            bar("2024-01-05", 4.0),
+        ], &["unchanged", "conflicted", "invalid", "created", "invalid"]).await;
+
+        assert_eq!(
+            (&report["created"], &report["unchanged"], &report["conflicted"], &report["invalid"]),
+            (&json!(1), &json!(1), &json!(1), &json!(2))
+        );
+        assert_eq!(report["results"][4]["reason"], "duplicate date in batch");
+        assert_eq!(close_on(&app, "2024-01-03").await, 1.0);
+        assert_eq!(close_on(&app, "2024-01-05").await, 3.0);
+    }
+}
+
+#[tokio::test]
+async fn upsert_batches_overwrite_stored_bars() {
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "AAPL").await;
+        add_quote(&app, "AAPL", "2024-01-02").await;
+        add_quote(&app, "AAPL", "2024-01-03").await;
+
+        let report: Value = send_batch(&app, "upsert", vec![
+            bar("2024-01-02", 1.0),
+            bar("2024-01-03", 2.0),
+            bar("2024-01-04", 3.0),
+        ], &["unchanged", "updated", "created"]).await;
+
+        assert_eq!((&report["created"], &report["updated"], &report["unchanged"]), (&json!(1), &json!(1), &json!(1)));
+        assert_eq!(close_on(&app, "2024-01-03").await, 2.0);
+        assert_eq!(close_on(&app, "2024-01-04").await, 3.0);
+    }
+}
+
+#[tokio::test]
+async fn malformed_batch_items_are_reported_one_by_one() {
+    let app: Router = app();
+    add_stock(&app, "AAPL").await;
+    let batch: String = json!([
+        { "date": "2024-01-02", "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0, "volume": 100 },
+        { "date": "2024-01-03", "open": "one", "high": 1.0, "low": 1.0, "close": 1.0, "volume": 100 },
+        { "open": 1.0 },
+    ])
+    .to_string();
+
+    let (status, body) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/history/batch", Some(&batch))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["created"], 1);
+    assert_eq!(body["invalid"], 2);
+    assert_eq!(body["results"][1]["date"], "2024-01-03");
+    assert_eq!(body["results"][1]["status"], "invalid");
+    assert!(body["results"][1]["reason"].as_str().unwrap().starts_with("open:"));
+    assert_eq!(body["results"][2]["date"], Value::Null);
+    assert_eq!(body["results"][2]["status"], "invalid");
+}
+
+#[tokio::test]
+async fn unknown_stocks_are_not_found() {
+    let app: Router = app();
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v1/stocks/NOPE", None)).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["message"], "stock NOPE not found");
+}
+
+#[tokio::test]
+async fn unknown_routes_are_not_found() {
+    let app: Router = app();
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v2/stocks", None)).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "no such route");
+}
+
+#[tokio::test]
+async fn deleting_a_stock_with_history_conflicts() {
+    let app: Router = app();
+    add_stock(&app, "AAPL").await;
+    add_quote(&app, "AAPL", "2024-01-02").await;
+
+    let (status, body) = send(&app, request(Method::DELETE, "/api/v1/stocks/AAPL", None)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "conflict");
+
+    let (status, _) = send(&app, request(Method::DELETE, "/api/v1/stocks/AAPL?cascade=true", None)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn deleting_a_stock_with_corporate_actions_conflicts() {
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "AAPL").await;
+        let split: String = json!({ "date": "2020-08-31", "numerator": 4.0, "denominator": 1.0 }).to_string();
+        let (status, _) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/splits", Some(&split))).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, body) = send(&app, request(Method::DELETE, "/api/v1/stocks/AAPL", None)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["message"].as_str().unwrap().starts_with("stock AAPL still has 1 "));
+
+        let (status, _) = send(&app, request(Method::GET, "/api/v1/stocks/AAPL/splits", None)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
+
+/// Adds a quote, a split and a dividend to `ticker`.
+async fn add_records(app: &Router, ticker: &str) {
+    add_quote(app, ticker, "2024-01-02").await;
+    let split: String = json!({ "date": "2020-08-31", "numerator": 4.0, "denominator": 1.0 }).to_string();
+    let (status, _) = send(app, request(Method::POST, &format!("/api/v1/stocks/{}/splits", ticker), Some(&split))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let dividend: String = json!({ "date": "2024-02-09", "amount": 0.24 }).to_string();
+    let (status, _) = send(app, request(Method::POST, &format!("/api/v1/stocks/{}/dividends", ticker), Some(&dividend))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+/// Statuses of the history, splits and dividends of `ticker`, and how many of each there are.
+async fn records(app: &Router, ticker: &str) -> Vec<(StatusCode, usize)> {
+    let mut records: Vec<(StatusCode, usize)> = Vec::new();
+    for (resource, field) in [("history", "data"), ("splits", "splits"), ("dividends", "dividends")] {
+        let uri: String = format!("/api/v1/stocks/{}/{}", ticker, resource);
+        let (status, body) = send(app, request(Method::GET, &uri, None)).await;
+        records.push((status, body[field].as_array().map_or(0, |items: &Vec<Value>| items.len())));
+    }
+    records
+}
+
+#[tokio::test]
+async fn renaming_a_stock_moves_its_records() {
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "FB").await;
+        add_records(&app, "FB").await;
+        let stock: String = json!({ "ticker": "META", "stock_exchange": "NASDAQ" }).to_string();
+
+        let (status, body) = send(&app, request(Method::PUT, "/api/v1/stocks/FB", Some(&stock))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ticker"], "META");
+
+        assert_eq!(records(&app, "META").await, vec![(StatusCode::OK, 1); 3]);
+        assert_eq!(records(&app, "FB").await, vec![(StatusCode::NOT_FOUND, 0); 3]);
+        let (status, _) = send(&app, request(Method::GET, "/api/v1/stocks/FB", None)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn a_refused_rename_moves_nothing() {
+    for app in [app(), sqlite_app()] {
+        add_stock(&app, "FB").await;
+        add_stock(&app, "META").await;
+        add_records(&app, "FB").await;
+        let stock: String = json!({ "ticker": "META", "stock_exchange": "NASDAQ" }).to_string();
+
+        let (status, _) = send(&app, request(Method::PUT, "/api/v1/stocks/FB", Some(&stock))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        assert_eq!(records(&app, "FB").await, vec![(StatusCode::OK, 1); 3]);
+        assert_eq!(records(&app, "META").await[1..], [(StatusCode::OK, 0); 2]);
+    }
+}
+
+#[tokio::test]
+async fn writing_records_of_unknown_stocks_is_not_found() {
+    for app in [app(), sqlite_app()] {
+        let writes: [(&str, Value); 4] = [
+            ("/api/v1/stocks/NOPE/history", bar("2024-01-02", 1.0)),
+            ("/api/v1/stocks/NOPE/history/batch", json!([bar("2024-01-02", 1.0)])),
+            ("/api/v1/stocks/NOPE/splits", json!({ "date": "2020-08-31", "numerator": 4.0, "denominator": 1.0 })),
+            ("/api/v1/stocks/NOPE/dividends", json!({ "date": "2024-02-09", "amount": 0.24 })),
+        ];
+
+        for (uri, body) in writes {
+            let (status, body) = send(&app, request(Method::POST, uri, Some(&body.to_string()))).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+            assert_eq!(body["message"], "stock NOPE not found", "{}", uri);
+        }
+        let (status, _) = send(&app, request(Method::PUT, "/api/v1/stocks/NOPE/history/2024-01-02", Some(&bar("2024-01-02", 1.0).to_string()))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(records(&app, "NOPE").await, vec![(StatusCode::NOT_FOUND, 0); 3]);
+    }
+}
+
+#[tokio::test]
+async fn renaming_onto_an_existing_ticker_conflicts() {
+    let app: Router = app();
+    add_stock(&app, "AAPL").await;
+    add_stock(&app, "MSFT").await;
+    let stock: String = json!({ "ticker": "MSFT", "stock_exchange": "NASDAQ" }).to_string();
+
+    let (status, body) = send(&app, request(Method::PUT, "/api/v1/stocks/AAPL", Some(&stock))).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["message"], "ticker MSFT is already in use");
+}
+
+#[tokio::test]
+async fn sqlite_data_survives_a_restart() {
+    let path: PathBuf = temp_db();
+    let app: Router = file_app(&path);
+    add_stock(&app, "AAPL").await;
+    add_stock(&app, "MSFT").await;
+    add_quote(&app, "AAPL", "2024-01-02").await;
+    add_quote(&app, "AAPL", "2024-01-03").await;
+    let split: String = json!({ "date": "2020-08-31", "numerator": 4.0, "denominator": 1.0 }).to_string();
+    send(&app, request(Method::POST, "/api/v1/stocks/AAPL/splits", Some(&split))).await;
+
+    let responses = |app: Router| async move {
+        let mut bodies: Vec<Value> = Vec::new();
+        for uri in ["/api/v1/stocks", "/api/v1/stocks/AAPL/history", "/api/v1/stocks/AAPL/splits"] {
+            let (status, body) = send(&app, request(Method::GET, uri, None)).await;
+            assert_eq!(status, StatusCode::OK);
+            bodies.push(body);
+        }
+        bodies
+    };
+    let before: Vec<Value> = responses(app).await;
+
+    let after: Vec<Value> = responses(file_app(&path)).await;
+    remove_db(&path);
+
+    assert_eq!(before[0].as_array().unwrap().len(), 2);
+    assert_eq!(before[1]["total"], 2);
+    assert_eq!(before, after);
+}
+
+#[tokio::test]
+async fn sqlite_databases_of_older_versions_are_migrated() {
+    let path: PathBuf = temp_db();
+    // A database written before stock metadata and corporate actions existed
+    let conn: rusqlite::Connection = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE stocks (ticker TEXT PRIMARY KEY NOT NULL, stock_exchange TEXT NOT NULL);
+        CREATE TABLE historical_data (
+            ticker TEXT NOT NULL, date TEXT NOT NULL, open REAL NOT NULL, high REAL NOT NULL,
+            low REAL NOT NULL, close REAL NOT NULL, volume INTEGER NOT NULL, PRIMARY KEY (ticker, date)
+        );
+        INSERT INTO stocks VALUES ('AAPL', 'NASDAQ');
+        INSERT INTO historical_data VALUES ('AAPL', '2024-01-02', 1, 1, 1, 1, 100);
+        PRAGMA user_version = 1;",
+    )
+    .unwrap();
+    drop(conn);
+
+    let app: Router = file_app(&path);
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v1/stocks/AAPL/history", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["volume"], 100);
+
+    let stock: String = json!({ "ticker": "AAPL", "stock_exchange": "NASDAQ", "currency": "USD" }).to_string();
+    let (status, body) = send(&app, request(Method::PUT, "/api/v1/stocks/AAPL", Some(&stock))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["currency"], "USD");
+
+    let dividend: String = json!({ "date": "2024-02-09", "amount": 0.24 }).to_string();
+    let (status, _) = send(&app, request(Method::POST, "/api/v1/stocks/AAPL/dividends", Some(&dividend))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let version: i64 = rusqlite::Connection::open(&path).unwrap()
+        .query_row("PRAGMA user_version", [], |row: &rusqlite::Row| row.get(0))
+        .unwrap();
+    drop(app);
+    // Opening an up-to-date database again leaves it as it is
+    let (_, body) = send(&file_app(&path), request(Method::GET, "/api/v1/stocks/AAPL/dividends", None)).await;
+    remove_db(&path);
+
+    assert_eq!(version, 3);
+    assert_eq!(body["dividends"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn storage_failures_are_internal_errors() {
+    let path: PathBuf = temp_db();
+    let app: Router = file_app(&path);
+
+    // Break the database behind the storage's back
+    rusqlite::Connection::open(&path).unwrap().execute("DROP TABLE stocks", []).unwrap();
+
+    let (status, body) = send(&app, request(Method::GET, "/api/v1/stocks", None)).await;
+    remove_db(&path);
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "internal_error");
+    assert_eq!(body["message"], "storage failure");
+}
+
+#[tokio::test]
+async fn errors_carry_the_request_id() {
+    let app: Router = app();
+    let request: Request<Body> = Request::builder()
+        .uri("/api/v1/stocks/NOPE")
+        .header("x-request-id", "test-request-1")
+        .body(Body::empty())
+        .unwrap();
+
+    let response: Response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "test-request-1");
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["request_id"], "test-request-1");
+}
+
+#[tokio::test]
+async fn requests_without_an_id_are_given_one() {
+    let app: Router = app();
+
+    let response: Response = app.oneshot(request(Method::GET, "/api/v1/stocks/NOPE", None)).await.unwrap();
+    let header: String = response.headers()["x-request-id"].to_str().unwrap().to_string();
+
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(!header.is_empty());
+    assert_eq!(body["request_id"], header.as_str());
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeSet, HashMap};
+use chrono::NaiveDate;
+use serde::Serialize;
+use proficalendar::TradingCalendar;
+use crate::models::{Dividend, HistoricalDataPoint, Split};
+
+/// How dry-run reports are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+/// A bar profiserve already holds with values other than the fetched ones.
+#[derive(Serialize, Debug)]
+pub struct ChangedBar {
+    pub stored: HistoricalDataPoint,
+    pub fetched: HistoricalDataPoint,
+}
+
+/// Consecutive trading days, both inclusive, with a bar neither in
+/// profiserve nor from the provider.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Gap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Number of trading days missing.
+    pub missing: usize,
+}
+
+/// What a synchronization would change for a single stock.
+#[derive(Serialize, Debug, Default)]
+pub struct StockDiff {
+    pub ticker: String,
+    /// Provider the bars would come from, if any had bars.
+    pub provider: Option<&'static str>,
+    pub created: Vec<HistoricalDataPoint>,
+    pub changed: Vec<ChangedBar>,
+    pub gaps: Vec<Gap>,
+    /// Splits and dividends profiserve is missing or holds with other values.
+    pub splits: Vec<Split>,
+    pub dividends: Vec<Dividend>,
+    /// Why the stock could not be synchronized.
+    pub error: Option<String>,
+}
+
+impl StockDiff {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Splits `fetched` into the bars profiserve is missing and the ones it holds
+/// with different values, and finds the gaps left between `fetched`'s first
+/// and last dates once both are merged, according to `calendar`.
+pub fn diff(
+    ticker: &str,
+    provider: &'static str,
+    fetched: &[HistoricalDataPoint],
+    stored: &[HistoricalDataPoint],
+    calendar: &TradingCalendar,
+) -> StockDiff {
+    let stored_by_date: HashMap<NaiveDate, &HistoricalDataPoint> = stored.iter()
+        .map(|dp: &HistoricalDataPoint| (dp.date, dp))
+        .collect();
+
+    let mut created: Vec<HistoricalDataPoint> = Vec::new();
+    let mut changed: Vec<ChangedBar> = Vec::new();
+
+    for bar in fetched {
+        match stored_by_date.get(&bar.date) {
+            None => created.push(bar.clone()),
+            Some(&stored_bar) if stored_bar != bar => changed.push(ChangedBar {
+                stored: stored_bar.clone(),
+                fetched: bar.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    created.sort_by_key(|dp: &HistoricalDataPoint| dp.date);
+    changed.sort_by_key(|bar: &ChangedBar| bar.fetched.date);
+
+    let gaps: Vec<Gap> = match (
+        fetched.iter().map(|dp: &HistoricalDataPoint| dp.date).min(),
+        fetched.iter().map(|dp: &HistoricalDataPoint| dp.date).max(),
+    ) {
+        (Some(first), Some(last)) => {
+            let dates: BTreeSet<NaiveDate> = fetched.iter()
+                .chain(stored)
+                .map(|dp: &HistoricalDataPoint| dp.date)
+                .filter(|date: &NaiveDate| (first..=last).contains(date))
+                .collect();
+            find_gaps(&dates, calendar)
+        }
+        _ => Vec::new(),
+    };
+
+    StockDiff {
+        ticker: ticker.to_string(),
+        provider: Some(provider),
+        created,
+        changed,
+        gaps,
+        splits: Vec::new(),
+        dividends: Vec::new(),
+        error: None,
+    }
+}
+
+/// Finds the runs of trading days missing between consecutive `dates`.
+pub fn find_gaps(dates: &BTreeSet<NaiveDate>, calendar: &TradingCalendar) -> Vec<Gap> {
+    let mut gaps: Vec<Gap> = Vec::new();
+
+    for (previous, next) in dates.iter().zip(dates.iter().skip(1)) {
+        let (Some(from), Some(to)) = (previous.succ_opt(), next.pred_opt()) else {
+            continue;
+        };
+        let missing: Vec<NaiveDate> = calendar.trading_days(from, to);
+
+        if let (Some(&from), Some(&to)) = (missing.first(), missing.last()) {
+            gaps.push(Gap {
+                from,
+                to,
+                missing: missing.len(),
+            });
+        }
+    }
+
+    gaps
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use serde_json::Value;
+use crate::market_data::{MarketDataProvider, ProviderCapabilities};
+use crate::models::{HistoricalDataPoint, Stock};
+
+/// Names of the columns (CSV) or keys (JSON) holding each field of a bar.
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    pub date: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    /// Bars without a volume column are given a volume of zero.
+    pub volume: String,
+    pub date_format: String,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            date: "date".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// Overrides column names from a spec such as `date=Day,close=Adj Close`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut mapping: Self = Self::default();
+
+        for entry in spec.split(',').map(str::trim).filter(|entry: &&str| !entry.is_empty()) {
+            let (field, column): (&str, &str) = entry.split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid column mapping entry: {}", entry))?;
+            let column: String = column.trim().to_string();
+
+            match field.trim() {
+                "date" => mapping.date = column,
+                "open" => mapping.open = column,
+                "high" => mapping.high = column,
+                "low" => mapping.low = column,
+                "close" => mapping.close = column,
+                "volume" => mapping.volume = column,
+                other => return Err(anyhow::anyhow!("Unknown field in column mapping: {}", other)),
+            }
+        }
+
+        Ok(mapping)
+    }
+}
+
+/// Reads bars from per-ticker CSV or JSON files dropped in a directory.
+///
+/// Files are named after the ticker (`ACME.csv`), optionally followed by an
+/// underscore and a suffix starting with a digit (`ACME_2024-06.json`) so
+/// that several drops can wait side by side. The digit keeps `BRK_B.csv` from
+/// being read for `BRK`. Once a stock has synced, the files read for it are
+/// moved to the archive directory.
+pub struct FileDropProvider {
+    directory: PathBuf,
+    archive_directory: PathBuf,
+    columns: ColumnMapping,
+    /// Files read for each ticker that have not been archived yet.
+    pending: Mutex<HashMap<String, Vec<PathBuf>>>,
+}
+
+impl FileDropProvider {
+    pub fn new(directory: PathBuf, archive_directory: PathBuf, columns: ColumnMapping) -> Self {
+        Self {
+            directory,
+            archive_directory,
+            columns,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lists the files waiting in the drop directory for `ticker`, oldest name first.
+    fn files_for(&self, ticker: &str) -> Result<Vec<PathBuf>> {
+        let entries: std::fs::ReadDir = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow::anyhow!("Failed to read {}: {}", self.directory.display(), e)),
+        };
+
+        let prefix: String = format!("{}_", ticker.to_lowercase());
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for entry in entries {
+            let path: PathBuf = entry?.path();
+            if !path.is_file() || file_format(&path).is_none() {
+                continue;
+            }
+
+            let stem: String = path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            let dated: bool = stem.strip_prefix(&prefix)
+                .is_some_and(|suffix: &str| suffix.starts_with(|c: char| c.is_ascii_digit()));
+            if stem == ticker.to_lowercase() || dated {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    fn parse_file(&self, path: &Path) -> Result<Vec<HistoricalDataPoint>> {
+        let contents: String = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+        let parsed: Result<Vec<HistoricalDataPoint>> = match file_format(path) {
+            Some(FileFormat::Csv) => self.parse_csv(&contents),
+            Some(FileFormat::Json) => self.parse_json(&contents),
+            None => Ok(Vec::new()),
+        };
+
+        parsed.map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+    }
+
+    fn parse_csv(&self, contents: &str) -> Result<Vec<HistoricalDataPoint>> {
+        let mut reader: csv::Reader<&[u8]> = csv::Reader::from_reader(contents.as_bytes());
+        let headers: csv::StringRecord = reader.headers()?.clone();
+
+        let column = |name: &str| headers.iter().position(|header: &str| header.trim().eq_ignore_ascii_case(name));
+        let required = |name: &str| column(name).ok_or_else(|| anyhow::anyhow!("missing column {}", name));
+
+        let date_column: usize = required(&self.columns.date)?;
+        let open_column: usize = required(&self.columns.open)?;
+        let high_column: usize = required(&self.columns.high)?;
+        let low_column: usize = required(&self.columns.low)?;
+        let close_column: usize = required(&self.columns.close)?;
+        let volume_column: Option<usize> = column(&self.columns.volume);
+
+        let mut data_points: Vec<HistoricalDataPoint> = Vec::new();
+
+        for (i, record) in reader.records().enumerate() {
+            let record: csv::StringRecord = record?;
+            let line: usize = i + 2;
+            let field = |index: usize| record.get(index).unwrap_or_default().trim();
+
+            data_points.push(HistoricalDataPoint {
+                date: self.parse_date(field(date_column), line)?,
+                open: parse_number(field(open_column), &self.columns.open, line)?,
+                high: parse_number(field(high_column), &self.columns.high, line)?,
+                low: parse_number(field(low_column), &self.columns.low, line)?,
+                close: parse_number(field(close_column), &self.columns.close, line)?,
+                volume: match volume_column {
+                    Some(index) if !field(index).is_empty() => parse_number(field(index), &self.columns.volume, line)?.round() as u64,
+                    _ => 0,
+                },
+            });
+        }
+
+        Ok(data_points)
+    }
+
+    /// Parses a JSON array of bar objects. Numbers may be given as strings.
+    fn parse_json(&self, contents: &str) -> Result<Vec<HistoricalDataPoint>> {
+        let bars: Vec<serde_json::Map<String, Value>> = serde_json::from_str(contents)?;
+        let mut data_points: Vec<HistoricalDataPoint> = Vec::new();
+
+        for (i, bar) in bars.iter().enumerate() {
+            let position: usize = i + 1;
+            let value = |name: &str| -> Option<String> {
+                let value: &Value = bar.get(name).or_else(|| {
+                    bar.iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| value)
+                })?;
+
+                match value {
+                    Value::String(s) => Some(s.trim().to_string()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                }
+            };
+            let required = |name: &str| value(name).ok_or_else(|| anyhow::anyhow!("missing {} in bar {}", name, position));
+
+            data_points.push(HistoricalDataPoint {
+                date: self.parse_date(&required(&self.columns.date)?, position)?,
+                open: parse_number(&required(&self.columns.open)?, &self.columns.open, position)?,
+                high: parse_number(&required(&self.columns.high)?, &self.columns.high, position)?,
+                low: parse_number(&required(&self.columns.low)?, &self.columns.low, position)?,
+                close: parse_number(&required(&self.columns.close)?, &self.columns.close, position)?,
+                volume: match value(&self.columns.volume) {
+                    Some(volume) => parse_number(&volume, &self.columns.volume, position)?.round() as u64,
+                    None => 0,
+                },
+            });
+        }
+
+        Ok(data_points)
+    }
+
+    fn parse_date(&self, value: &str, position: usize) -> Result<NaiveDate> {
+        NaiveDate::parse_from_str(value, &self.columns.date_format)
+            .map_err(|e| anyhow::anyhow!("invalid date {:?} at {}: {}", value, position, e))
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for FileDropProvider {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            full_history: true,
+            metadata: false,
+            corporate_actions: false,
+            ignores_range: true,
+        }
+    }
+
+    /// Returns every bar of the files dropped for `stock`, whatever the
+    /// range, since the files are archived once the stock has synced.
+    async fn fetch_daily_bars(
+        &self,
+        stock: &Stock,
+        _from: Option<NaiveDate>,
+        _to: NaiveDate,
+    ) -> Result<Vec<HistoricalDataPoint>> {
+        let files: Vec<PathBuf> = self.files_for(&stock.ticker)?;
+
+        // Later files win when several of them hold the same date
+        let mut bars: HashMap<NaiveDate, HistoricalDataPoint> = HashMap::new();
+        for file in &files {
+            for bar in self.parse_file(file)? {
+                bars.insert(bar.date, bar);
+            }
+        }
+
+        self.pending.lock().unwrap().insert(stock.ticker.clone(), files);
+
+        let mut data_points: Vec<HistoricalDataPoint> = bars.into_values().collect();
+        data_points.sort_by_key(|dp: &HistoricalDataPoint| dp.date);
+
+        Ok(data_points)
+    }
+
+    /// Archives the files read for `stock`, prefixed with the time they were
+    /// processed so that repeated drops under the same name do not collide.
+    async fn after_sync(&self, stock: &Stock) -> Result<()> {
+        let files: Vec<PathBuf> = self.pending.lock().unwrap().remove(&stock.ticker).unwrap_or_default();
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.archive_directory)?;
+        let processed_at: String = Utc::now().format("%Y%m%dT%H%M%S").to_string();
+
+        for file in files {
+            let file_name: &str = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            let archived: PathBuf = self.archive_directory.join(format!("{}_{}", processed_at, file_name));
+
+            std::fs::rename(&file, &archived)
+                .map_err(|e| anyhow::anyhow!("Failed to archive {}: {}", file.display(), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+enum FileFormat {
+    Csv,
+    Json,
+}
+
+fn file_format(path: &Path) -> Option<FileFormat> {
+    let extension: String = path.extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        "csv" => Some(FileFormat::Csv),
+        "json" => Some(FileFormat::Json),
+        _ => None,
+    }
+}
+
+fn parse_number(value: &str, column: &str, position: usize) -> Result<f64> {
+    value.parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("invalid {} {:?} at {}", column, value, position))
+}
@@ -0,0 +1,11 @@
+pub mod diff;
+pub mod file_drop;
+pub mod market_data;
+pub mod models;
+pub mod profiserve_client;
+pub mod rate_limit;
+pub mod reconciliation;
+pub mod retry;
+pub mod stooq;
+pub mod yahoo_finance;
+pub mod sync_service;
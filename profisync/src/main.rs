@@ -1,17 +1,70 @@
-mod models;
-mod profiserve_client;
-mod yahoo_finance;
-mod sync_service;
-
+use std::process::ExitCode;
+use std::time::Duration;
 use anyhow::Result;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 use console::style;
-use sync_service::SyncService;
+use proficalendar::{calendars_file, Calendars};
+use profisync::diff::OutputFormat;
+use profisync::market_data::ProviderChains;
+use profisync::retry::RetryPolicy;
+use profisync::sync_service::{SyncOptions, SyncService, SyncSummary};
+
+/// Synchronizes daily quotes from market data providers into profiserve.
+/// Everything but the command is configured through environment variables.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Fetch and compare without writing to profiserve, reporting the quotes
+    /// that would be created or changed and the gaps left
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Format of the dry-run report
+    #[arg(long, global = true, value_enum, default_value_t = Format::Human, requires = "dry_run")]
+    format: Format,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Synchronizes every SYNC_INTERVAL_SECS until stopped (the default)
+    Run,
+    /// Runs a single synchronization pass, exiting with a failure status if any stock failed
+    Once,
+    /// Synchronizes one stock over a date range, filling dates before its latest quote
+    Backfill {
+        /// Ticker of a stock already in profiserve
+        #[arg(long)]
+        ticker: String,
+        /// First date of the range, such as 2020-01-31
+        #[arg(long)]
+        from: NaiveDate,
+        /// Last date of the range, the latest session of the stock's exchange by default
+        #[arg(long)]
+        to: Option<NaiveDate>,
+    },
+    /// Runs a single synchronization pass that also fetches the trading days missing from stored history
+    FillGaps,
+    /// Shows how far behind each stock in profiserve is and the gaps in its history
+    Status,
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    println!("\n{}", style("═".repeat(60)).cyan());
-    println!("{}", style("    📈 Profitis Sync Service").cyan().bold());
-    println!("{}\n", style("═".repeat(60)).cyan());
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let output: OutputFormat = match cli.format {
+        Format::Human => OutputFormat::Human,
+        Format::Json => OutputFormat::Json,
+    };
 
     let profiserve_url = std::env::var("PROFISERVE_URL")
         .unwrap_or_else(|_| "http://127.0.0.1:3000".to_string());
@@ -21,22 +74,184 @@ async fn main() -> Result<()> {
         .parse::<u64>()
         .unwrap_or(60);
 
-    println!("{}", style("Configuration:").bold().underlined());
-    println!("  {} {}", 
-        style("Profiserve URL:").dim(),
-        style(&profiserve_url).cyan()
-    );
-    println!("  {} {} ({} minutes)", 
-        style("Sync interval:").dim(),
-        style(format!("{} seconds", sync_interval_secs)).cyan(),
-        style(sync_interval_secs / 60).yellow()
-    );
-    println!();
-
-    let sync_service = SyncService::new(profiserve_url, sync_interval_secs);
-    
-    sync_service.start().await?;
+    let batch_size = std::env::var("SYNC_BATCH_SIZE")
+        .unwrap_or_else(|_| "500".to_string())
+        .parse::<usize>()
+        .unwrap_or(500);
+
+    let workers = std::env::var("SYNC_WORKERS")
+        .unwrap_or_else(|_| "4".to_string())
+        .parse::<usize>()
+        .unwrap_or(4);
+
+    let retry_defaults = RetryPolicy::default();
+    let retry_policy = RetryPolicy {
+        max_attempts: std::env::var("SYNC_RETRY_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(retry_defaults.max_attempts)
+            .max(1),
+        base_delay: std::env::var("SYNC_RETRY_BASE_DELAY_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(retry_defaults.base_delay),
+        max_delay: std::env::var("SYNC_RETRY_MAX_DELAY_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(retry_defaults.max_delay),
+    };
+
+    let calendars_path = calendars_file();
+    let calendars = match &calendars_path {
+        Some(path) => Calendars::load(path)?,
+        None => Calendars::builtin(),
+    };
+
+    let providers = ProviderChains::from_specs(
+        &std::env::var("SYNC_PROVIDER").unwrap_or_else(|_| "yahoo".to_string()),
+        &std::env::var("SYNC_PROVIDERS_BY_EXCHANGE").unwrap_or_default(),
+        &std::env::var("SYNC_PROVIDERS_BY_TICKER").unwrap_or_default(),
+        &retry_policy,
+        &calendars,
+    )?;
+
+    let reconcile_tolerance = std::env::var("SYNC_RECONCILE_TOLERANCE")
+        .ok()
+        .and_then(|value| value.parse::<f64>().ok());
+
+    let lookback_days = std::env::var("SYNC_LOOKBACK_DAYS")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u32>()
+        .unwrap_or(0);
+
+    let fill_gaps = std::env::var("SYNC_FILL_GAPS")
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+        || matches!(cli.command, Some(Command::FillGaps));
+
+    let empty_gap_retry = std::env::var("SYNC_EMPTY_GAP_RETRY_HOURS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|hours| Duration::from_secs(hours * 60 * 60))
+        .unwrap_or(SyncOptions::default().empty_gap_retry);
+
+    let enrich_metadata = std::env::var("SYNC_ENRICH_METADATA")
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    // Keep stdout to the report alone for JSON output
+    if output == OutputFormat::Human {
+        println!("\n{}", style("═".repeat(60)).cyan());
+        println!("{}", style("    📈 Profitis Sync Service").cyan().bold());
+        println!("{}\n", style("═".repeat(60)).cyan());
+
+        println!("{}", style("Configuration:").bold().underlined());
+        println!("  {} {}", 
+            style("Profiserve URL:").dim(),
+            style(&profiserve_url).cyan()
+        );
+        println!("  {} {} ({} routes)", 
+            style("Market data providers:").dim(),
+            style(providers.describe_default()).cyan(),
+            style(providers.route_count()).yellow()
+        );
+        println!("  {} {}", 
+            style("Reconciliation:").dim(),
+            style(match reconcile_tolerance {
+                Some(tolerance) => format!("closes within {}%", tolerance * 100.0),
+                None => "off".to_string(),
+            }).cyan()
+        );
+        println!("  {} {}", 
+            style("Revision look-back:").dim(),
+            style(match lookback_days {
+                0 => "off".to_string(),
+                days => format!("last {} days", days),
+            }).cyan()
+        );
+        println!("  {} {}", 
+            style("Trading calendars:").dim(),
+            style(match &calendars_path {
+                Some(path) => format!("built-in and {}", path.display()),
+                None => "built-in".to_string(),
+            }).cyan()
+        );
+        println!("  {} {}", 
+            style("Fill gaps:").dim(),
+            style(if fill_gaps {
+                format!("yes, empty gaps retried after {} hours", empty_gap_retry.as_secs() / 3600)
+            } else {
+                "no".to_string()
+            }).cyan()
+        );
+        println!("  {} {} ({} minutes)", 
+            style("Sync interval:").dim(),
+            style(format!("{} seconds", sync_interval_secs)).cyan(),
+            style(sync_interval_secs / 60).yellow()
+        );
+        println!("  {} {}", 
+            style("Upload batch size:").dim(),
+            style(format!("{} quotes", batch_size)).cyan()
+        );
+        println!("  {} {}", 
+            style("Workers:").dim(),
+            style(format!("{} stocks at a time", workers)).cyan()
+        );
+        println!("  {} {} attempts, backoff from {} ms up to {} ms", 
+            style("Retries:").dim(),
+            style(retry_policy.max_attempts).cyan(),
+            style(retry_policy.base_delay.as_millis()).cyan(),
+            style(retry_policy.max_delay.as_millis()).cyan()
+        );
+        println!("  {} {}", 
+            style("Enrich metadata:").dim(),
+            style(if enrich_metadata { "yes" } else { "no" }).cyan()
+        );
+        if cli.dry_run {
+            println!("  {} {}", 
+                style("Dry run:").dim(),
+                style("nothing will be written to profiserve").yellow()
+            );
+        }
+        println!();
+    }
+
+    let options = SyncOptions {
+        sync_interval: Duration::from_secs(sync_interval_secs),
+        batch_size,
+        workers,
+        enrich_metadata,
+        fill_gaps,
+        empty_gap_retry,
+        lookback_days,
+        reconcile_tolerance,
+        dry_run: cli.dry_run,
+        output,
+        calendars,
+    };
+    let sync_service = SyncService::new(profiserve_url, providers, options, retry_policy);
+
+    let summary: SyncSummary = match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            sync_service.start().await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Once | Command::FillGaps => sync_service.sync_all_stocks().await?,
+        Command::Backfill { ticker, from, to } => {
+            sync_service.backfill(&ticker, from, to).await?
+        }
+        Command::Status => {
+            sync_service.status().await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+
+    if summary.failed > 0 {
+        return Ok(ExitCode::FAILURE);
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use proficalendar::Calendars;
+use crate::file_drop::{ColumnMapping, FileDropProvider};
+use crate::models::{CorporateActions, HistoricalDataPoint, Stock};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::stooq::StooqClient;
+use crate::yahoo_finance::YahooFinanceClient;
+
+/// What a provider can do beyond fetching daily bars.
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderCapabilities {
+    /// Whether the provider can return a ticker's whole history when no start date is given.
+    pub full_history: bool,
+    /// Whether [`MarketDataProvider::fetch_metadata`] can fill in stock metadata.
+    pub metadata: bool,
+    /// Whether [`MarketDataProvider::fetch_corporate_actions`] returns splits and dividends.
+    pub corporate_actions: bool,
+    /// Whether every bar returned must be stored even outside the requested
+    /// range, because the provider cannot hand it over again later.
+    pub ignores_range: bool,
+}
+
+/// A source of daily OHLCV bars.
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// Short name used in logs and configuration.
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Limit the provider's client applies to its own requests, if any.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
+    /// Fetches the daily bars of `stock` between `from` and `to`, both
+    /// inclusive. Without `from` the whole available history is returned.
+    async fn fetch_daily_bars(
+        &self,
+        stock: &Stock,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+    ) -> Result<Vec<HistoricalDataPoint>>;
+
+    /// Returns a copy of `stock` with the metadata fields it is missing filled
+    /// in, or `None` when the provider has nothing to add.
+    async fn fetch_metadata(&self, _stock: &Stock) -> Result<Option<Stock>> {
+        Ok(None)
+    }
+
+    /// Fetches the splits and dividends of `stock` between `from` and `to`,
+    /// both inclusive, or `None` when the provider does not know about them.
+    async fn fetch_corporate_actions(
+        &self,
+        _stock: &Stock,
+        _from: Option<NaiveDate>,
+        _to: NaiveDate,
+    ) -> Result<Option<CorporateActions>> {
+        Ok(None)
+    }
+
+    /// Called once `stock` has been synchronized without errors, so that the
+    /// provider can release or archive what it fetched for it.
+    async fn after_sync(&self, _stock: &Stock) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Ordered lists of providers to try for each stock. A stock uses the chain
+/// configured for its ticker, then the one for its exchange, then the default.
+pub struct ProviderChains {
+    default_chain: Vec<Arc<dyn MarketDataProvider>>,
+    by_exchange: HashMap<String, Vec<Arc<dyn MarketDataProvider>>>,
+    by_ticker: HashMap<String, Vec<Arc<dyn MarketDataProvider>>>,
+}
+
+impl ProviderChains {
+    /// Uses the same chain for every stock.
+    pub fn new(default_chain: Vec<Arc<dyn MarketDataProvider>>) -> Self {
+        Self {
+            default_chain,
+            by_exchange: HashMap::new(),
+            by_ticker: HashMap::new(),
+        }
+    }
+
+    /// Builds the chains from specs such as `yahoo,stooq` for the default
+    /// chain and `LSE=stooq,yahoo;XETRA=stooq` for the per-exchange and
+    /// per-ticker ones. Each provider is only built once.
+    pub fn from_specs(
+        default_spec: &str,
+        exchange_spec: &str,
+        ticker_spec: &str,
+        retry_policy: &RetryPolicy,
+        calendars: &Calendars,
+    ) -> Result<Self> {
+        let mut providers: HashMap<String, Arc<dyn MarketDataProvider>> = HashMap::new();
+
+        let default_chain = build_chain(default_spec, &mut providers, retry_policy, calendars)?;
+        if default_chain.is_empty() {
+            return Err(anyhow::anyhow!("The default provider chain is empty"));
+        }
+
+        let mut build_routes = |spec: &str| -> Result<HashMap<String, Vec<Arc<dyn MarketDataProvider>>>> {
+            let mut routes = HashMap::new();
+            for route in spec.split(';').map(str::trim).filter(|route: &&str| !route.is_empty()) {
+                let (key, chain) = route.split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("Invalid provider route: {}", route))?;
+                routes.insert(key.trim().to_uppercase(), build_chain(chain, &mut providers, retry_policy, calendars)?);
+            }
+            Ok(routes)
+        };
+
+        let by_exchange = build_routes(exchange_spec)?;
+        let by_ticker = build_routes(ticker_spec)?;
+
+        Ok(Self {
+            default_chain,
+            by_exchange,
+            by_ticker,
+        })
+    }
+
+    pub fn chain_for(&self, stock: &Stock) -> &[Arc<dyn MarketDataProvider>] {
+        self.by_ticker.get(&stock.ticker.to_uppercase())
+            .or_else(|| self.by_exchange.get(&stock.stock_exchange.to_uppercase()))
+            .unwrap_or(&self.default_chain)
+    }
+
+    /// Describes the default chain, such as `yahoo (2/s, burst 2) → stooq (1/s, burst 1)`.
+    pub fn describe_default(&self) -> String {
+        describe_chain(&self.default_chain)
+    }
+
+    /// Number of exchanges and tickers with their own chain.
+    pub fn route_count(&self) -> usize {
+        self.by_exchange.len() + self.by_ticker.len()
+    }
+}
+
+fn describe_chain(chain: &[Arc<dyn MarketDataProvider>]) -> String {
+    chain.iter()
+        .map(|provider: &Arc<dyn MarketDataProvider>| match provider.rate_limit() {
+            Some(rate_limit) => format!("{} ({})", provider.name(), rate_limit),
+            None => provider.name().to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" → ")
+}
+
+fn build_chain(
+    spec: &str,
+    providers: &mut HashMap<String, Arc<dyn MarketDataProvider>>,
+    retry_policy: &RetryPolicy,
+    calendars: &Calendars,
+) -> Result<Vec<Arc<dyn MarketDataProvider>>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|name: &&str| !name.is_empty())
+        .map(|name: &str| {
+            if let Some(provider) = providers.get(name) {
+                return Ok(provider.clone());
+            }
+
+            let provider = provider_from_name(name, retry_policy, calendars)?;
+            providers.insert(name.to_string(), provider.clone());
+            Ok(provider)
+        })
+        .collect()
+}
+
+/// Builds a single provider from its configured name. `calendars` give the
+/// time zones of exchanges to providers that need them.
+pub fn provider_from_name(name: &str, retry_policy: &RetryPolicy, calendars: &Calendars) -> Result<Arc<dyn MarketDataProvider>> {
+    match name {
+        "yahoo" => {
+            let base_url = std::env::var("YAHOO_BASE_URL")
+                .unwrap_or_else(|_| "https://query1.finance.yahoo.com".to_string());
+            let rate_limit = RateLimit::parse(&std::env::var("YAHOO_RATE_LIMIT")
+                .unwrap_or_else(|_| "2/s,60/m,burst=2".to_string()))?;
+
+            Ok(Arc::new(YahooFinanceClient::new(base_url, retry_policy.clone(), RateLimiter::new(rate_limit))
+                .with_calendars(calendars.clone())))
+        }
+        "stooq" => {
+            let base_url = std::env::var("STOOQ_BASE_URL")
+                .unwrap_or_else(|_| "https://stooq.com".to_string());
+            let rate_limit = RateLimit::parse(&std::env::var("STOOQ_RATE_LIMIT")
+                .unwrap_or_else(|_| "1/s".to_string()))?;
+
+            Ok(Arc::new(StooqClient::new(base_url, retry_policy.clone(), RateLimiter::new(rate_limit))))
+        }
+        "file" => {
+            let directory = PathBuf::from(std::env::var("FILE_DROP_DIR")
+                .unwrap_or_else(|_| "drop".to_string()));
+            let archive_directory = std::env::var("FILE_DROP_ARCHIVE_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| directory.join("archive"));
+
+            let mut columns = ColumnMapping::parse(&std::env::var("FILE_DROP_COLUMNS").unwrap_or_default())?;
+            if let Ok(date_format) = std::env::var("FILE_DROP_DATE_FORMAT") {
+                columns.date_format = date_format;
+            }
+
+            Ok(Arc::new(FileDropProvider::new(directory, archive_directory, columns)))
+        }
+        other => Err(anyhow::anyhow!("Unknown market data provider: {}", other)),
+    }
+}
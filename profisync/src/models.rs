@@ -1,14 +1,44 @@
+use std::collections::HashMap;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Stock {
     pub ticker: String,
     pub stock_exchange: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub asset_class: Option<AssetClass>,
+    #[serde(default)]
+    pub sector: Option<String>,
+    #[serde(default)]
+    pub industry: Option<String>,
+    #[serde(default)]
+    pub isin: Option<String>,
+    #[serde(default)]
+    pub figi: Option<String>,
+    #[serde(default)]
+    pub listed_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub delisted_on: Option<NaiveDate>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetClass {
+    Equity,
+    Etf,
+    Index,
+    Crypto,
+    Fx,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct HistoricalDataPoint {
-    pub date: String,
+    pub date: NaiveDate,
     pub open: f64,
     pub high: f64,
     pub low: f64,
@@ -16,10 +46,106 @@ pub struct HistoricalDataPoint {
     pub volume: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A stock split effective on `date`, where `denominator` shares became
+/// `numerator` shares.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Split {
+    pub date: NaiveDate,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// A cash dividend per share, dated on its ex-dividend date.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Dividend {
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+/// Splits and dividends of a stock, each sorted by date.
+#[derive(Debug, Default)]
+pub struct CorporateActions {
+    pub splits: Vec<Split>,
+    pub dividends: Vec<Dividend>,
+}
+
+impl CorporateActions {
+    pub fn len(&self) -> usize {
+        self.splits.len() + self.dividends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Body of `GET /api/v1/stocks/:ticker/splits`.
+#[derive(Deserialize, Debug)]
+pub struct SplitList {
+    pub splits: Vec<Split>,
+}
+
+/// Body of `GET /api/v1/stocks/:ticker/dividends`.
+#[derive(Deserialize, Debug)]
+pub struct DividendList {
+    pub dividends: Vec<Dividend>,
+}
+
+/// A page of `GET /api/v1/stocks/:ticker/history`.
+#[derive(Deserialize, Debug)]
 pub struct HistoricalDataList {
-    pub ticker: String,
     pub data: Vec<HistoricalDataPoint>,
+    pub next_cursor: Option<NaiveDate>,
+}
+
+/// Consecutive trading days, both inclusive, profiserve has no bars for.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HistoryGap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Number of trading days missing.
+    pub missing: usize,
+}
+
+/// Gap analysis of `GET /api/v1/stocks/:ticker/history/gaps`.
+#[derive(Deserialize, Debug)]
+pub struct HistoryGapList {
+    /// Trading days expected between the first and last stored dates.
+    pub expected: usize,
+    pub stored: usize,
+    pub gaps: Vec<HistoryGap>,
+}
+
+/// Body of profiserve error responses.
+#[derive(Deserialize, Debug)]
+pub struct ApiErrorBody {
+    pub code: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemStatus {
+    Created,
+    Updated,
+    Unchanged,
+    Conflicted,
+    Invalid,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatchItemResult {
+    pub date: Option<NaiveDate>,
+    pub status: BatchItemStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatchReport {
+    pub created: usize,
+    pub results: Vec<BatchItemResult>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -29,13 +155,65 @@ pub struct YahooFinanceResponse {
 
 #[derive(Deserialize, Debug)]
 pub struct Chart {
-    pub result: Vec<ChartResult>,
+    /// Null when Yahoo reports an error instead.
+    pub result: Option<Vec<ChartResult>>,
+    pub error: Option<ChartError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChartError {
+    pub code: String,
+    pub description: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ChartResult {
+    #[serde(default)]
+    pub meta: Option<ChartMeta>,
+    #[serde(default)]
     pub timestamp: Vec<i64>,
     pub indicators: Indicators,
+    /// Only sent when asked for with `events=div,splits`.
+    #[serde(default)]
+    pub events: Option<ChartEvents>,
+}
+
+/// Corporate actions of a chart, keyed by the Unix timestamp of their day.
+#[derive(Deserialize, Debug, Default)]
+pub struct ChartEvents {
+    #[serde(default)]
+    pub dividends: HashMap<String, ChartDividend>,
+    #[serde(default)]
+    pub splits: HashMap<String, ChartSplit>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChartDividend {
+    pub amount: f64,
+    pub date: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChartSplit {
+    pub date: i64,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// Instrument details Yahoo sends alongside every chart.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartMeta {
+    pub currency: Option<String>,
+    pub instrument_type: Option<String>,
+    /// Unix timestamp of the first trade.
+    pub first_trade_date: Option<i64>,
+    pub long_name: Option<String>,
+    pub short_name: Option<String>,
+    /// Offset of the exchange from UTC in seconds, at the time of the request.
+    pub gmtoffset: Option<i32>,
+    /// IANA time zone of the exchange, such as `Asia/Tokyo`.
+    pub exchange_timezone_name: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -43,11 +221,18 @@ pub struct Indicators {
     pub quote: Vec<Quote>,
 }
 
+/// Daily values, aligned with [`ChartResult::timestamp`]. Yahoo sends an
+/// empty object when the range has no bars.
 #[derive(Deserialize, Debug)]
 pub struct Quote {
+    #[serde(default)]
     pub open: Vec<Option<f64>>,
+    #[serde(default)]
     pub high: Vec<Option<f64>>,
+    #[serde(default)]
     pub low: Vec<Option<f64>>,
+    #[serde(default)]
     pub close: Vec<Option<f64>>,
+    #[serde(default)]
     pub volume: Vec<Option<u64>>,
 }
@@ -1,44 +1,104 @@
 use anyhow::Result;
-use crate::models::{Stock, HistoricalDataPoint, HistoricalDataList};
+use chrono::NaiveDate;
+use crate::models::{
+    Stock, HistoricalDataPoint, HistoricalDataList, HistoryGapList, BatchReport, ApiErrorBody,
+    Split, SplitList, Dividend, DividendList,
+};
+use crate::retry::RetryPolicy;
 
 pub struct ProfiserveClient {
     base_url: String,
     client: reqwest::Client,
+    retry_policy: RetryPolicy,
 }
 
 impl ProfiserveClient {
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, retry_policy: RetryPolicy) -> Self {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            retry_policy,
         }
     }
 
     pub async fn get_stocks(&self) -> Result<Vec<Stock>> {
         let url = format!("{}/api/v1/stocks", self.base_url);
         
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self.retry_policy
+            .send(self.client.get(&url))
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Failed to fetch stocks: {}",
-                response.status()
-            ));
+            return Err(api_error(response, "Failed to fetch stocks".to_string()).await);
         }
 
         let stocks: Vec<Stock> = response.json().await?;
         Ok(stocks)
     }
 
-    pub async fn get_historical_data(&self, ticker: &str) -> Result<Option<HistoricalDataList>> {
-        let url = format!("{}/api/v1/stocks/{}/history", self.base_url, ticker);
+    pub async fn update_stock(&self, ticker: &str, stock: &Stock) -> Result<Stock> {
+        let url = format!("{}/api/v1/stocks/{}", self.base_url, ticker);
+
+        let response = self.retry_policy
+            .send(self.client.put(&url).json(stock))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to update stock {}", ticker)).await);
+        }
+
+        let stock: Stock = response.json().await?;
+        Ok(stock)
+    }
+
+    /// Uploads data points in a single request. Dates that already exist are
+    /// left untouched and reported as unchanged or conflicted.
+    pub async fn create_historical_data_batch(
+        &self,
+        ticker: &str,
+        data_points: &[HistoricalDataPoint],
+    ) -> Result<BatchReport> {
+        let url = format!("{}/api/v1/stocks/{}/history/batch", self.base_url, ticker);
         
-        let response = self.client
-            .get(&url)
-            .send()
+        // Inserts leave dates that already exist untouched, so replaying
+        // a batch whose response was lost is harmless
+        let request = self.client
+            .post(&url)
+            .query(&[("mode", "insert")])
+            .json(data_points);
+
+        let response = self.retry_policy
+            .send_deduplicated(request)
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to create historical data for {}", ticker)).await);
+        }
+
+        let report: BatchReport = response.json().await?;
+        Ok(report)
+    }
+
+    /// Overwrites the bar profiserve holds for `data_point`'s date.
+    pub async fn update_historical_data_point(&self, ticker: &str, data_point: &HistoricalDataPoint) -> Result<()> {
+        let url = format!("{}/api/v1/stocks/{}/history/{}", self.base_url, ticker, data_point.date);
+
+        let response = self.retry_policy
+            .send(self.client.put(&url).json(data_point))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to update historical data for {} on {}", ticker, data_point.date)).await);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_latest_date(&self, ticker: &str) -> Result<Option<NaiveDate>> {
+        let url = format!("{}/api/v1/stocks/{}/history/latest", self.base_url, ticker);
+
+        let response = self.retry_policy
+            .send(self.client.get(&url))
             .await?;
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
@@ -46,69 +106,196 @@ impl ProfiserveClient {
         }
 
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Failed to fetch historical data for {}: {}",
-                ticker,
-                response.status()
-            ));
+            return Err(api_error(response, format!("Failed to fetch latest data for {}", ticker)).await);
         }
 
-        let data: HistoricalDataList = response.json().await?;
-        Ok(Some(data))
+        let latest: HistoricalDataPoint = response.json().await?;
+        Ok(Some(latest.date))
     }
 
-    pub async fn create_historical_data_point(
-        &self,
-        ticker: &str,
-        data_point: &HistoricalDataPoint,
-    ) -> Result<()> {
+    /// Returns the bars profiserve holds for `ticker` between `from` and `to`,
+    /// both inclusive, oldest first.
+    pub async fn get_historical_data(&self, ticker: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<HistoricalDataPoint>> {
         let url = format!("{}/api/v1/stocks/{}/history", self.base_url, ticker);
-        
-        let response = self.client
-            .post(&url)
-            .json(data_point)
-            .send()
+
+        let mut data_points: Vec<HistoricalDataPoint> = Vec::new();
+        let mut cursor: Option<NaiveDate> = None;
+
+        loop {
+            let mut request = self.client.get(&url).query(&[("from", from), ("to", to)]);
+            if let Some(cursor) = cursor {
+                request = request.query(&[("cursor", cursor)]);
+            }
+
+            let response = self.retry_policy
+                .send(request)
+                .await?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(data_points);
+            }
+
+            if !response.status().is_success() {
+                return Err(api_error(response, format!("Failed to fetch historical data for {}", ticker)).await);
+            }
+
+            let page: HistoricalDataList = response.json().await?;
+            data_points.extend(page.data);
+
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => return Ok(data_points),
+            }
+        }
+    }
+
+    /// Returns the trading days missing from the stored history of `ticker`,
+    /// or `None` when it has no history yet.
+    pub async fn get_gaps(&self, ticker: &str) -> Result<Option<HistoryGapList>> {
+        let url = format!("{}/api/v1/stocks/{}/history/gaps", self.base_url, ticker);
+
+        let response = self.retry_policy
+            .send(self.client.get(&url))
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to fetch gaps for {}", ticker)).await);
+        }
+
+        let gaps: HistoryGapList = response.json().await?;
+        Ok(Some(gaps))
+    }
+
+    /// Returns the splits profiserve holds for `ticker` up to `to`, from
+    /// `from` if given, oldest first.
+    pub async fn get_splits(&self, ticker: &str, from: Option<NaiveDate>, to: NaiveDate) -> Result<Vec<Split>> {
+        let url = format!("{}/api/v1/stocks/{}/splits", self.base_url, ticker);
+
+        let response = self.retry_policy
+            .send(self.client.get(&url).query(&date_range(from, to)))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to fetch splits for {}", ticker)).await);
+        }
+
+        let splits: SplitList = response.json().await?;
+        Ok(splits.splits)
+    }
+
+    /// Records a split. A split already recorded for its date is left untouched.
+    pub async fn create_split(&self, ticker: &str, split: &Split) -> Result<()> {
+        let url = format!("{}/api/v1/stocks/{}/splits", self.base_url, ticker);
+
+        // A split already recorded for the date is left untouched, so
+        // replaying a request whose response was lost is harmless
+        let response = self.retry_policy
+            .send_deduplicated(self.client.post(&url).json(split))
             .await?;
 
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Failed to create historical data for {}: {}",
-                ticker,
-                response.status()
-            ));
+            return Err(api_error(response, format!("Failed to create split for {} on {}", ticker, split.date)).await);
         }
 
         Ok(())
     }
 
-    pub async fn get_latest_date(&self, ticker: &str) -> Result<Option<String>> {
-        match self.get_historical_data(ticker).await? {
-            Some(data) => {
-                if data.data.is_empty() {
-                    Ok(None)
-                } else {
-                    let latest = data.data.iter()
-                        .map(|dp| dp.date.as_str())
-                        .max()
-                        .map(String::from);
-                    Ok(latest)
-                }
-            }
-            None => Ok(None),
+    /// Overwrites the split profiserve holds for `split`'s date.
+    pub async fn update_split(&self, ticker: &str, split: &Split) -> Result<()> {
+        let url = format!("{}/api/v1/stocks/{}/splits/{}", self.base_url, ticker, split.date);
+
+        let response = self.retry_policy
+            .send(self.client.put(&url).json(split))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to update split for {} on {}", ticker, split.date)).await);
         }
+
+        Ok(())
     }
 
-    pub async fn get_existing_dates(&self, ticker: &str) -> Result<std::collections::HashSet<String>> {
-        use std::collections::HashSet;
-        
-        match self.get_historical_data(ticker).await? {
-            Some(data) => {
-                let dates: HashSet<String> = data.data.iter()
-                    .map(|dp| dp.date.clone())
-                    .collect();
-                Ok(dates)
+    /// Returns the dividends profiserve holds for `ticker` up to `to`, from
+    /// `from` if given, oldest first.
+    pub async fn get_dividends(&self, ticker: &str, from: Option<NaiveDate>, to: NaiveDate) -> Result<Vec<Dividend>> {
+        let url = format!("{}/api/v1/stocks/{}/dividends", self.base_url, ticker);
+
+        let response = self.retry_policy
+            .send(self.client.get(&url).query(&date_range(from, to)))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to fetch dividends for {}", ticker)).await);
+        }
+
+        let dividends: DividendList = response.json().await?;
+        Ok(dividends.dividends)
+    }
+
+    /// Records a dividend. A dividend already recorded for its date is left untouched.
+    pub async fn create_dividend(&self, ticker: &str, dividend: &Dividend) -> Result<()> {
+        let url = format!("{}/api/v1/stocks/{}/dividends", self.base_url, ticker);
+
+        // A dividend already recorded for the date is left untouched, so
+        // replaying a request whose response was lost is harmless
+        let response = self.retry_policy
+            .send_deduplicated(self.client.post(&url).json(dividend))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to create dividend for {} on {}", ticker, dividend.date)).await);
+        }
+
+        Ok(())
+    }
+
+    /// Overwrites the dividend profiserve holds for `dividend`'s date.
+    pub async fn update_dividend(&self, ticker: &str, dividend: &Dividend) -> Result<()> {
+        let url = format!("{}/api/v1/stocks/{}/dividends/{}", self.base_url, ticker, dividend.date);
+
+        let response = self.retry_policy
+            .send(self.client.put(&url).json(dividend))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, format!("Failed to update dividend for {} on {}", ticker, dividend.date)).await);
+        }
+
+        Ok(())
+    }
+
+}
+
+/// Query parameters of a date range, without a lower bound when `from` is unset.
+fn date_range(from: Option<NaiveDate>, to: NaiveDate) -> Vec<(&'static str, NaiveDate)> {
+    let mut range: Vec<(&'static str, NaiveDate)> = vec![("to", to)];
+    if let Some(from) = from {
+        range.push(("from", from));
+    }
+    range
+}
+
+/// Builds an error from a failed profiserve response, including the reason
+/// given in its JSON error body when there is one.
+async fn api_error(response: reqwest::Response, context: String) -> anyhow::Error {
+    let status = response.status();
+
+    match response.json::<ApiErrorBody>().await {
+        Ok(body) => {
+            let mut message = format!("{}: {} - {} [{}", context, status, body.message, body.code);
+            if let Some(details) = body.details {
+                message.push_str(&format!(", details: {}", details));
+            }
+            if let Some(request_id) = body.request_id {
+                message.push_str(&format!(", request {}", request_id));
             }
-            None => Ok(HashSet::new()),
+            message.push(']');
+            anyhow::anyhow!(message)
         }
+        Err(_) => anyhow::anyhow!("{}: {}", context, status),
     }
 }
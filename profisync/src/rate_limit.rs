@@ -0,0 +1,167 @@
+//! Client-side rate limiting of upstream providers with token buckets.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+
+/// How fast requests may be made to a provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimit {
+    pub per_second: Option<f64>,
+    pub per_minute: Option<f64>,
+    /// Requests that may be made back to back before the rates apply.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Parses a spec such as `2/s,100/m,burst=5`. An empty spec or `none`
+    /// means no limit.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut limit = Self {
+            burst: 1,
+            ..Self::default()
+        };
+
+        let spec = spec.trim();
+        if spec.is_empty() || spec.eq_ignore_ascii_case("none") {
+            return Ok(limit);
+        }
+
+        for part in spec.split(',').map(str::trim) {
+            let invalid = || anyhow::anyhow!("Invalid rate limit: {}", part);
+
+            if let Some(burst) = part.strip_prefix("burst=") {
+                limit.burst = burst.parse::<u32>().map_err(|_| invalid())?.max(1);
+            } else if let Some((rate, unit)) = part.split_once('/') {
+                let rate = rate.trim().parse::<f64>().ok().filter(|rate: &f64| *rate > 0.0).ok_or_else(invalid)?;
+
+                match unit.trim() {
+                    "s" => limit.per_second = Some(rate),
+                    "m" => limit.per_minute = Some(rate),
+                    _ => return Err(invalid()),
+                }
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(limit)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.per_second.is_none() && self.per_minute.is_none()
+    }
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_unlimited() {
+            return write!(f, "unlimited");
+        }
+
+        let rates: Vec<String> = [(self.per_second, "s"), (self.per_minute, "m")]
+            .into_iter()
+            .filter_map(|(rate, unit): (Option<f64>, &str)| rate.map(|rate: f64| format!("{}/{}", rate, unit)))
+            .collect();
+
+        write!(f, "{}, burst {}", rates.join(", "), self.burst)
+    }
+}
+
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    /// Tokens added per second.
+    refill_rate: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(rate_per_second: f64, capacity: f64) -> Self {
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_rate: rate_per_second,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed: f64 = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Time until the bucket holds a whole token.
+    fn wait_time(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.refill_rate)
+        }
+    }
+}
+
+/// Token buckets enforcing a [`RateLimit`], shared by every request made
+/// through the provider client that owns it.
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<Vec<Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let burst: f64 = f64::from(limit.burst.max(1));
+
+        let mut buckets: Vec<Bucket> = Vec::new();
+        if let Some(per_second) = limit.per_second {
+            buckets.push(Bucket::new(per_second, burst));
+        }
+        if let Some(per_minute) = limit.per_minute {
+            buckets.push(Bucket::new(per_minute / 60.0, burst.min(per_minute.max(1.0))));
+        }
+
+        Self {
+            limit,
+            buckets: Mutex::new(buckets),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(RateLimit::default())
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Waits until a request may be made and takes a token from every bucket.
+    pub async fn acquire(&self) {
+        loop {
+            let wait: Duration = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let now: Instant = Instant::now();
+
+                for bucket in buckets.iter_mut() {
+                    bucket.refill(now);
+                }
+
+                let wait: Duration = buckets.iter()
+                    .map(Bucket::wait_time)
+                    .max()
+                    .unwrap_or(Duration::ZERO);
+
+                if wait.is_zero() {
+                    for bucket in buckets.iter_mut() {
+                        bucket.tokens -= 1.0;
+                    }
+                    return;
+                }
+
+                wait
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use crate::models::HistoricalDataPoint;
+
+/// A date on which two providers disagree on the close by more than the tolerance.
+#[derive(Debug)]
+pub struct CloseMismatch {
+    pub date: NaiveDate,
+    pub primary_close: f64,
+    pub secondary_close: f64,
+    /// Difference relative to the primary close.
+    pub relative_difference: f64,
+}
+
+/// Comparison of the bars two providers returned for the same stock and range.
+#[derive(Debug)]
+pub struct ReconciliationReport {
+    pub ticker: String,
+    pub primary: &'static str,
+    pub secondary: &'static str,
+    /// Number of dates both providers returned a bar for.
+    pub compared: usize,
+    pub mismatches: Vec<CloseMismatch>,
+}
+
+/// Compares the closes of the dates present in both `primary_bars` and
+/// `secondary_bars`, flagging those whose relative difference exceeds `tolerance`.
+pub fn reconcile(
+    ticker: &str,
+    primary: (&'static str, &[HistoricalDataPoint]),
+    secondary: (&'static str, &[HistoricalDataPoint]),
+    tolerance: f64,
+) -> ReconciliationReport {
+    let (primary_name, primary_bars) = primary;
+    let (secondary_name, secondary_bars) = secondary;
+
+    let secondary_closes: HashMap<NaiveDate, f64> = secondary_bars.iter()
+        .map(|dp: &HistoricalDataPoint| (dp.date, dp.close))
+        .collect();
+
+    let mut compared: usize = 0;
+    let mut mismatches: Vec<CloseMismatch> = Vec::new();
+
+    for bar in primary_bars {
+        let Some(&secondary_close) = secondary_closes.get(&bar.date) else {
+            continue;
+        };
+        compared += 1;
+
+        let relative_difference: f64 = if bar.close == 0.0 {
+            if secondary_close == 0.0 { 0.0 } else { f64::INFINITY }
+        } else {
+            ((secondary_close - bar.close) / bar.close).abs()
+        };
+
+        if relative_difference > tolerance {
+            mismatches.push(CloseMismatch {
+                date: bar.date,
+                primary_close: bar.close,
+                secondary_close,
+                relative_difference,
+            });
+        }
+    }
+
+    mismatches.sort_by_key(|mismatch: &CloseMismatch| mismatch.date);
+
+    ReconciliationReport {
+        ticker: ticker.to_string(),
+        primary: primary_name,
+        secondary: secondary_name,
+        compared,
+        mismatches,
+    }
+}
@@ -0,0 +1,165 @@
+//! Retries of failed HTTP requests with exponential backoff.
+
+use std::future::Future;
+use std::time::Duration;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use console::style;
+use indicatif::ProgressBar;
+use rand::Rng;
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode};
+use crate::rate_limit::RateLimiter;
+
+tokio::task_local! {
+    static PROGRESS: ProgressBar;
+}
+
+/// Runs `future` with retries reported above `pb` instead of on stderr.
+pub async fn with_progress<F: Future>(pb: ProgressBar, future: F) -> F::Output {
+    PROGRESS.scope(pb, future).await
+}
+
+fn report(message: String) {
+    if PROGRESS.try_with(|pb: &ProgressBar| pb.println(&message)).is_err() {
+        eprintln!("{}", message);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts made in total, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each following one.
+    pub base_delay: Duration,
+    /// Upper bound of any delay, including those asked for with `Retry-After`.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes every request exactly once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Sends `request`, retrying it on network errors and retryable statuses
+    /// if its method is idempotent.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.send_with(request, false, None).await
+    }
+
+    /// Like [`send`](Self::send), waiting on `rate_limiter` before every attempt.
+    pub async fn send_limited(&self, request: RequestBuilder, rate_limiter: &RateLimiter) -> Result<Response> {
+        self.send_with(request, false, Some(rate_limiter)).await
+    }
+
+    /// Sends a non-idempotent `request` the server deduplicates, such as a
+    /// batch insert, retrying it like an idempotent one.
+    pub async fn send_deduplicated(&self, request: RequestBuilder) -> Result<Response> {
+        self.send_with(request, true, None).await
+    }
+
+    async fn send_with(
+        &self,
+        request: RequestBuilder,
+        deduplicated: bool,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Result<Response> {
+        let (client, request) = request.build_split();
+        let request: Request = request?;
+
+        let retryable: bool = deduplicated || is_idempotent(request.method());
+        let mut attempt: u32 = 1;
+
+        loop {
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.acquire().await;
+            }
+
+            // Requests with streaming bodies cannot be replayed
+            let Some(next) = request.try_clone().filter(|_| retryable && attempt < self.max_attempts) else {
+                return Ok(client.execute(request).await?);
+            };
+
+            let (delay, reason): (Duration, String) = match client.execute(next).await {
+                Ok(response) if !is_retryable_status(response.status()) => return Ok(response),
+                Ok(response) => (
+                    self.retry_after(&response).unwrap_or_else(|| self.backoff(attempt)),
+                    response.status().to_string(),
+                ),
+                Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => (self.backoff(attempt), e.to_string()),
+                Err(e) => return Err(e.into()),
+            };
+
+            report(format!("    {} Retrying {} {} in {:.1}s (attempt {}/{}): {}",
+                style("↻").yellow(),
+                request.method(),
+                style(request.url().path()).dim(),
+                delay.as_secs_f64(),
+                attempt + 1,
+                self.max_attempts,
+                style(reason).dim()
+            ));
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Exponential backoff with full jitter: a random delay between zero and
+    /// `base_delay * 2^(attempt - 1)`, capped at `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling: Duration = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    /// Delay asked for by a 429 or 503 response, given either in seconds or
+    /// as an HTTP date.
+    fn retry_after(&self, response: &Response) -> Option<Duration> {
+        if !matches!(response.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE) {
+            return None;
+        }
+
+        let value: &str = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+        let delay: Duration = match value.parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => {
+                let at: DateTime<Utc> = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+                (at - Utc::now()).to_std().unwrap_or(Duration::ZERO)
+            }
+        };
+
+        Some(delay.min(self.max_delay))
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
@@ -0,0 +1,157 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use crate::market_data::{MarketDataProvider, ProviderCapabilities};
+use crate::models::{HistoricalDataPoint, Stock};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::retry::RetryPolicy;
+
+pub struct StooqClient {
+    base_url: String,
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    rate_limiter: RateLimiter,
+}
+
+/// One line of Stooq's daily CSV. Indices come without a volume column.
+#[derive(Deserialize, Debug)]
+struct StooqRow {
+    #[serde(rename = "Date")]
+    date: NaiveDate,
+    #[serde(rename = "Open")]
+    open: f64,
+    #[serde(rename = "High")]
+    high: f64,
+    #[serde(rename = "Low")]
+    low: f64,
+    #[serde(rename = "Close")]
+    close: f64,
+    #[serde(rename = "Volume", default)]
+    volume: Option<f64>,
+}
+
+impl StooqClient {
+    pub fn new(base_url: String, retry_policy: RetryPolicy, rate_limiter: RateLimiter) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            retry_policy,
+            rate_limiter,
+        }
+    }
+
+    /// Downloads and parses the daily CSV of a Stooq symbol, such as `aapl.us`.
+    pub async fn fetch_csv(
+        &self,
+        symbol: &str,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+    ) -> Result<Vec<HistoricalDataPoint>> {
+        let url = format!("{}/q/d/l/", self.base_url);
+
+        let mut query: Vec<(&str, String)> = vec![
+            ("s", symbol.to_string()),
+            ("i", "d".to_string()),
+            ("d2", to.format("%Y%m%d").to_string()),
+        ];
+        if let Some(from) = from {
+            query.push(("d1", from.format("%Y%m%d").to_string()));
+        }
+
+        let response = self.retry_policy
+            .send_limited(self.client.get(&url).query(&query), &self.rate_limiter)
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Stooq API error: {}",
+                response.status()
+            ));
+        }
+
+        let body = response.text().await?;
+
+        parse_csv(&body)
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for StooqClient {
+    fn name(&self) -> &'static str {
+        "stooq"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            full_history: true,
+            metadata: false,
+            corporate_actions: false,
+            ignores_range: false,
+        }
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(self.rate_limiter.limit())
+    }
+
+    async fn fetch_daily_bars(
+        &self,
+        stock: &Stock,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+    ) -> Result<Vec<HistoricalDataPoint>> {
+        self.fetch_csv(&stooq_symbol(stock), from, to).await
+    }
+}
+
+/// Parses a Stooq daily CSV. Stooq answers `No data` instead of an empty CSV
+/// when the symbol has no bars in the requested range.
+fn parse_csv(body: &str) -> Result<Vec<HistoricalDataPoint>> {
+    if body.trim().is_empty() || body.trim().eq_ignore_ascii_case("no data") {
+        return Ok(Vec::new());
+    }
+
+    let mut reader = csv::Reader::from_reader(body.as_bytes());
+    let mut data_points = Vec::new();
+
+    for (i, row) in reader.deserialize::<StooqRow>().enumerate() {
+        let row = row.map_err(|e| anyhow::anyhow!("Invalid Stooq CSV on line {}: {}", i + 2, e))?;
+
+        data_points.push(HistoricalDataPoint {
+            date: row.date,
+            open: row.open,
+            high: row.high,
+            low: row.low,
+            close: row.close,
+            volume: row.volume.unwrap_or(0.0).round() as u64,
+        });
+    }
+
+    Ok(data_points)
+}
+
+/// Maps a stock to its Stooq symbol, which carries the market as a suffix
+/// (`AAPL` on NASDAQ is `aapl.us`). Tickers that already have a suffix and
+/// exchanges Stooq does not know are passed through as they are.
+fn stooq_symbol(stock: &Stock) -> String {
+    let ticker = stock.ticker.to_lowercase();
+    if ticker.contains('.') || ticker.starts_with('^') {
+        return ticker;
+    }
+
+    let suffix = match stock.stock_exchange.to_uppercase().as_str() {
+        "NASDAQ" | "NYSE" | "AMEX" | "NYSEARCA" | "NYSEAMERICAN" | "BATS" => Some("us"),
+        "LSE" => Some("uk"),
+        "XETRA" | "FRA" => Some("de"),
+        "TSE" | "JPX" => Some("jp"),
+        "HKEX" => Some("hk"),
+        // Warsaw, Stooq's home market, has no suffix
+        _ => None,
+    };
+
+    match suffix {
+        Some(suffix) => format!("{}.{}", ticker, suffix),
+        None => ticker,
+    }
+}
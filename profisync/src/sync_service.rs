@@ -1,31 +1,195 @@
 use anyhow::Result;
-use chrono::{NaiveDate, Utc};
-use std::collections::HashSet;
-use std::time::Duration;
+use chrono::{Days, NaiveDate, Utc};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::time::{self, Interval};
 use indicatif::{ProgressBar, ProgressStyle, MultiProgress};
 use console::style;
-use crate::models::{HistoricalDataPoint, Stock};
+use serde::Serialize;
+use proficalendar::{Calendars, TradingCalendar};
+use crate::diff::{self, ChangedBar, OutputFormat, StockDiff};
+use crate::market_data::{MarketDataProvider, ProviderChains};
+use crate::models::{BatchItemResult, BatchItemStatus, CorporateActions, Dividend, HistoricalDataPoint, HistoryGapList, Split, Stock};
 use crate::profiserve_client::ProfiserveClient;
-use crate::yahoo_finance::YahooFinanceClient;
+use crate::reconciliation::{self, ReconciliationReport};
+use crate::retry::{self, RetryPolicy};
 
 pub struct SyncService {
     profiserve_client: ProfiserveClient,
-    yahoo_client: YahooFinanceClient,
-    sync_interval: Duration,
+    providers: ProviderChains,
+    options: SyncOptions,
+    /// Gaps, by ticker and first and last day, that no provider had any bar
+    /// for, and when, so that later passes do not ask for them again until
+    /// [`SyncOptions::empty_gap_retry`] has passed.
+    empty_gaps: Mutex<HashMap<(String, NaiveDate, NaiveDate), Instant>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    pub sync_interval: Duration,
+    /// Quotes uploaded per batch request.
+    pub batch_size: usize,
+    /// Stocks synchronized at the same time.
+    pub workers: usize,
+    pub enrich_metadata: bool,
+    /// Fetch the trading days missing from stored history again after
+    /// synchronizing each stock.
+    pub fill_gaps: bool,
+    /// How long a gap no provider had any bar for is left alone before
+    /// being asked for again.
+    pub empty_gap_retry: Duration,
+    /// Days of stored history fetched again on every pass so that bars the
+    /// provider revised since are corrected. Off when zero.
+    pub lookback_days: u32,
+    /// Relative close difference above which providers are flagged as
+    /// disagreeing. Reconciliation is off when unset.
+    pub reconcile_tolerance: Option<f64>,
+    /// Fetch and compare without writing anything to profiserve, reporting
+    /// what would change instead.
+    pub dry_run: bool,
+    /// How dry-run reports are printed.
+    pub output: OutputFormat,
+    /// Trading calendars deciding which bars are expected for each stock.
+    pub calendars: Calendars,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            sync_interval: Duration::from_secs(60),
+            batch_size: 500,
+            workers: 4,
+            enrich_metadata: false,
+            fill_gaps: false,
+            empty_gap_retry: Duration::from_secs(24 * 60 * 60),
+            lookback_days: 0,
+            reconcile_tolerance: None,
+            dry_run: false,
+            output: OutputFormat::Human,
+            calendars: Calendars::builtin(),
+        }
+    }
+}
+
+/// Number of stocks in each state at the end of a synchronization pass.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub synchronized: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+/// How the synchronization of a single stock ended.
+enum SyncOutcome {
+    UpToDate,
+    /// No provider had bars for the range. `exhaustive` when every provider
+    /// of the chain answered, rather than some of them failing.
+    NoNewData { exhaustive: bool },
+    AlreadyStored,
+    Synchronized {
+        provider: &'static str,
+        created: usize,
+        revised: usize,
+    },
+    /// What a dry run would have written.
+    DryRun(StockDiff),
+}
+
+
+struct StockSync {
+    outcome: SyncOutcome,
+    reports: Vec<ReconciliationReport>,
+    /// Stored bars overwritten with the values the provider revised them to.
+    revisions: Vec<ChangedBar>,
+    /// Quotes created, or that would be on a dry run, inside gaps.
+    gaps_filled: usize,
+    /// Splits and dividends recorded, or that would be on a dry run.
+    corporate_actions: usize,
+    /// Splits and dividends profiserve refused to record.
+    failed_corporate_actions: usize,
+    /// Providers whose bars ended up stored, the only ones that may release
+    /// or archive what they fetched for the stock.
+    sources: Vec<Arc<dyn MarketDataProvider>>,
+}
+
+/// Splits and dividends written to profiserve for a range, or that would be
+/// on a dry run, and how many profiserve refused.
+#[derive(Default)]
+struct ActionsSync {
+    recorded: CorporateActions,
+    failed: usize,
+}
+
+impl StockSync {
+    fn new(outcome: SyncOutcome) -> Self {
+        Self {
+            outcome,
+            reports: Vec::new(),
+            revisions: Vec::new(),
+            gaps_filled: 0,
+            corporate_actions: 0,
+            failed_corporate_actions: 0,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Adds `provider` to the sources unless it is already one of them.
+    fn add_source(&mut self, provider: Arc<dyn MarketDataProvider>) {
+        if !self.sources.iter().any(|source| source.name() == provider.name()) {
+            self.sources.push(provider);
+        }
+    }
+
+    /// Whether profiserve was, or would have been, written to.
+    fn changes_data(&self) -> bool {
+        let outcome_changes_data: bool = match &self.outcome {
+            SyncOutcome::Synchronized { .. } => true,
+            SyncOutcome::DryRun(diff) => !diff.is_empty(),
+            _ => false,
+        };
+
+        outcome_changes_data || self.gaps_filled > 0 || self.corporate_actions > 0
+    }
+}
+
+/// What the providers of a chain answered for a range.
+enum Fetched {
+    Bars(FetchedBars),
+    /// No provider had bars. `exhaustive` when none of them failed either.
+    Nothing { exhaustive: bool },
+}
+
+/// Bars returned by the first provider of a chain that had any.
+struct FetchedBars {
+    provider: Arc<dyn MarketDataProvider>,
+    /// Position of the provider in the stock's chain.
+    position: usize,
+    data_points: Vec<HistoricalDataPoint>,
 }
 
 impl SyncService {
-    pub fn new(profiserve_url: String, sync_interval_secs: u64) -> Self {
+    pub fn new(
+        profiserve_url: String,
+        providers: ProviderChains,
+        options: SyncOptions,
+        retry_policy: RetryPolicy,
+    ) -> Self {
         Self {
-            profiserve_client: ProfiserveClient::new(profiserve_url),
-            yahoo_client: YahooFinanceClient::new(),
-            sync_interval: Duration::from_secs(sync_interval_secs),
+            profiserve_client: ProfiserveClient::new(profiserve_url, retry_policy),
+            providers,
+            options: SyncOptions {
+                batch_size: options.batch_size.max(1),
+                workers: options.workers.max(1),
+                ..options
+            },
+            empty_gaps: Mutex::new(HashMap::new()),
         }
     }
 
     pub async fn start(&self) -> Result<()> {
-        let mut interval: Interval = time::interval(self.sync_interval);
+        let mut interval: Interval = time::interval(self.options.sync_interval);
 
         loop {
             interval.tick().await;
@@ -43,7 +207,10 @@ impl SyncService {
         }
     }
 
-    async fn sync_all_stocks(&self) -> Result<()> {
+    /// Runs a single synchronization pass over every stock in profiserve.
+    /// Stocks that fail are counted in the summary rather than returned as
+    /// an error, which is kept for profiserve being unreachable.
+    pub async fn sync_all_stocks(&self) -> Result<SyncSummary> {
         let spinner: ProgressBar = ProgressBar::new_spinner();
         spinner.set_style(
             ProgressStyle::default_spinner()
@@ -56,19 +223,21 @@ impl SyncService {
         let stocks: Vec<Stock> = self.profiserve_client.get_stocks().await?;
         spinner.finish_and_clear();
         
-        if stocks.is_empty() {
-            println!("{} {}", 
-                style("ℹ").blue().bold(),
-                style("No stocks found in profiserve").yellow()
-            );
-            return Ok(());
+        if self.options.output == OutputFormat::Human {
+            if stocks.is_empty() {
+                println!("{} {}", 
+                    style("ℹ").blue().bold(),
+                    style("No stocks found in profiserve").yellow()
+                );
+            } else {
+                println!("{} Found {} stocks to {}\n", 
+                    style("📊").bold(),
+                    style(stocks.len()).cyan().bold(),
+                    if self.options.dry_run { "compare" } else { "synchronize" }
+                );
+            }
         }
 
-        println!("{} Found {} stocks to synchronize\n", 
-            style("📊").bold(),
-            style(stocks.len()).cyan().bold()
-        );
-
         let multi: MultiProgress = MultiProgress::new();
         let overall_pb: ProgressBar = multi.add(ProgressBar::new(stocks.len() as u64));
         overall_pb.set_style(
@@ -77,26 +246,176 @@ impl SyncService {
                 .unwrap()
                 .progress_chars("█▓▒░  ")
         );
+        overall_pb.set_message(format!("({} workers)", self.options.workers));
+
+        let started_at: Instant = Instant::now();
+
+        // Stocks finish in any order, their results are put back in the
+        // order of the stock list for the summary
+        let mut results: Vec<(usize, Result<StockSync>)> = stream::iter(stocks.iter().enumerate())
+            .map(|(index, stock): (usize, &Stock)| {
+                let multi: &MultiProgress = &multi;
+                let overall_pb: &ProgressBar = &overall_pb;
+
+                async move {
+                    let result: Result<StockSync> = self.sync_stock(stock, None, multi).await;
+                    overall_pb.inc(1);
+                    (index, result)
+                }
+            })
+            .buffer_unordered(self.options.workers)
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _): &(usize, Result<StockSync>)| *index);
+
+        overall_pb.finish_and_clear();
+
+        Ok(self.print_results(&stocks, &results, started_at.elapsed()))
+    }
+
+    /// Fetches the bars of `ticker` between `from` and `to` and uploads the
+    /// ones profiserve is missing, including dates before its latest one.
+    /// `to` defaults to the latest session of the stock's exchange.
+    pub async fn backfill(&self, ticker: &str, from: NaiveDate, to: Option<NaiveDate>) -> Result<SyncSummary> {
+        let stocks: Vec<Stock> = self.profiserve_client.get_stocks().await?
+            .into_iter()
+            .filter(|stock: &Stock| stock.ticker.eq_ignore_ascii_case(ticker))
+            .collect();
+        if stocks.is_empty() {
+            return Err(anyhow::anyhow!("Stock {} not found in profiserve", ticker));
+        }
+
+        let to: NaiveDate = to.unwrap_or_else(|| {
+            self.options.calendars.for_exchange(&stocks[0].stock_exchange).latest_session(Utc::now())
+        });
+        if from > to {
+            return Err(anyhow::anyhow!("Backfill range starts after it ends ({} > {})", from, to));
+        }
+
+        if self.options.output == OutputFormat::Human {
+            println!("{} Backfilling {} from {} to {}\n", 
+                style("📊").bold(),
+                style(&stocks[0].ticker).cyan().bold(),
+                style(from).cyan(),
+                style(to).cyan()
+            );
+        }
+
+        let started_at: Instant = Instant::now();
+        let multi: MultiProgress = MultiProgress::new();
+        let result: Result<StockSync> = self.sync_stock(&stocks[0], Some((from, to)), &multi).await;
+
+        Ok(self.print_results(&stocks, &[(0, result)], started_at.elapsed()))
+    }
+
+    /// Prints the summary of a pass, then the reconciliation reports when
+    /// reconciliation is on and what would change on a dry run. JSON output
+    /// only prints the dry-run report.
+    fn print_results(&self, stocks: &[Stock], results: &[(usize, Result<StockSync>)], elapsed: Duration) -> SyncSummary {
+        let summary: SyncSummary = summarize(results);
+
+        if self.options.output == OutputFormat::Json {
+            print_json_report(stocks, results, summary);
+            return summary;
+        }
+
+        print_summary(stocks, results, summary, self.options.dry_run, elapsed);
+
+        if self.options.reconcile_tolerance.is_some() {
+            let reports: Vec<&ReconciliationReport> = results.iter()
+                .filter_map(|(_, result): &(usize, Result<StockSync>)| result.as_ref().ok())
+                .flat_map(|sync: &StockSync| &sync.reports)
+                .collect();
+
+            print_reconciliation(&reports);
+        }
+
+        let revisions: Vec<(&str, &ChangedBar)> = results.iter()
+            .filter_map(|(index, result): &(usize, Result<StockSync>)| {
+                result.as_ref().ok().map(|sync: &StockSync| (stocks[*index].ticker.as_str(), &sync.revisions))
+            })
+            .flat_map(|(ticker, revisions): (&str, &Vec<ChangedBar>)| revisions.iter().map(move |bar: &ChangedBar| (ticker, bar)))
+            .collect();
+        if !revisions.is_empty() {
+            print_revisions(&revisions);
+        }
+
+        if self.options.dry_run {
+            let diffs: Vec<&StockDiff> = results.iter()
+                .filter_map(|(_, result): &(usize, Result<StockSync>)| match result {
+                    Ok(StockSync { outcome: SyncOutcome::DryRun(diff), .. }) => Some(diff),
+                    _ => None,
+                })
+                .collect();
+
+            print_diffs(&diffs);
+        }
+
+        summary
+    }
+
+    /// Prints the latest date profiserve has for each stock, how many days
+    /// behind it is and the providers it is synchronized from.
+    pub async fn status(&self) -> Result<()> {
+        let stocks: Vec<Stock> = self.profiserve_client.get_stocks().await?;
+        println!("{} {} stocks in profiserve\n", 
+            style("📊").bold(),
+            style(stocks.len()).cyan().bold()
+        );
 
         for stock in &stocks {
-            overall_pb.set_message(format!("Processing {}", style(&stock.ticker).cyan()));
-            
-            if let Err(e) = self.sync_stock(&stock, &multi).await {
-                println!("{} {} - {}", 
+            let ticker = style(format!("{:<10}", stock.ticker)).cyan().bold();
+            let calendar: &TradingCalendar = self.options.calendars.for_exchange(&stock.stock_exchange);
+            let providers: String = self.providers.chain_for(stock).iter()
+                .map(|provider: &Arc<dyn MarketDataProvider>| provider.name())
+                .collect::<Vec<&str>>()
+                .join(" → ");
+
+            match self.profiserve_client.get_latest_date(&stock.ticker).await {
+                Ok(Some(latest)) => {
+                    println!("  {} {} {} {} {}", 
+                        style("✓").green().bold(),
+                        ticker,
+                        style(latest).green(),
+                        style(format!("({} sessions behind)", sessions_behind(calendar, latest))).dim(),
+                        style(providers).dim()
+                    );
+
+                    match self.profiserve_client.get_gaps(&stock.ticker).await {
+                        Ok(Some(gaps)) => print_gaps(&gaps),
+                        Ok(None) => {}
+                        Err(e) => println!("      {} {}", 
+                            style("✗").red(),
+                            style(format!("{}", e)).dim()
+                        ),
+                    }
+                }
+                Ok(None) => println!("  {} {} {} {}", 
+                    style("⚠").yellow().bold(),
+                    ticker,
+                    style("No history yet").yellow(),
+                    style(providers).dim()
+                ),
+                Err(e) => println!("  {} {} {}", 
                     style("✗").red().bold(),
-                    style(&stock.ticker).red(),
+                    ticker,
                     style(format!("{}", e)).dim()
-                );
+                ),
             }
-            
-            overall_pb.inc(1);
         }
 
-        overall_pb.finish_and_clear();
         Ok(())
     }
 
-    async fn sync_stock(&self, stock: &Stock, multi: &MultiProgress) -> Result<()> {
+    /// Uploads the bars profiserve is missing for `stock`, showing progress
+    /// in a bar of its own until it is done. Without a `range`, only the bars
+    /// after the latest stored one are fetched.
+    async fn sync_stock(
+        &self,
+        stock: &Stock,
+        range: Option<(NaiveDate, NaiveDate)>,
+        multi: &MultiProgress,
+    ) -> Result<StockSync> {
         let pb: ProgressBar = multi.add(ProgressBar::new_spinner());
         pb.set_style(
             ProgressStyle::default_spinner()
@@ -104,101 +423,863 @@ impl SyncService {
                 .unwrap()
         );
         pb.enable_steady_tick(Duration::from_millis(100));
+
+        let result: Result<StockSync> = retry::with_progress(pb.clone(), self.sync_stock_with_progress(stock, range, &pb)).await;
+
+        // Providers only compared against must keep what they fetched
+        let sources: &[Arc<dyn MarketDataProvider>] = result.as_ref()
+            .map_or(&[], |sync: &StockSync| sync.sources.as_slice());
+        if !self.options.dry_run {
+            for provider in sources {
+                if let Err(e) = provider.after_sync(stock).await {
+                    pb.println(format!("{} {} - {}", 
+                        style("⚠").yellow().bold(),
+                        style(&stock.ticker).yellow(),
+                        style(format!("{}", e)).dim()
+                    ));
+                }
+            }
+        }
+        pb.finish_and_clear();
+
+        result
+    }
+
+    async fn sync_stock_with_progress(
+        &self,
+        stock: &Stock,
+        range: Option<(NaiveDate, NaiveDate)>,
+        pb: &ProgressBar,
+    ) -> Result<StockSync> {
+        let chain: &[Arc<dyn MarketDataProvider>] = self.providers.chain_for(stock);
+
+        if self.options.enrich_metadata && !self.options.dry_run {
+            pb.set_message(format!("{} Fetching metadata...", style(&stock.ticker).cyan().bold()));
+
+            if let Err(e) = self.enrich_stock(stock, chain).await {
+                pb.println(format!("    {} Failed to update metadata: {}", 
+                    style("⚠").yellow(),
+                    style(format!("{}", e)).dim()
+                ));
+            }
+        }
+
+        if let Some((from, to)) = range {
+            return self.sync_range(stock, chain, Some(from), to, pb).await;
+        }
+
         pb.set_message(format!("{} Checking latest data...", style(&stock.ticker).cyan().bold()));
 
-        let latest_date: Option<String> = self.profiserve_client.get_latest_date(&stock.ticker).await?;
+        let latest_date: Option<NaiveDate> = self.profiserve_client.get_latest_date(&stock.ticker).await?;
+        let calendar: &TradingCalendar = self.options.calendars.for_exchange(&stock.stock_exchange);
+        // Bars of sessions still open would only be partial
+        let latest_session: NaiveDate = calendar.latest_session(Utc::now());
 
-        let new_data_points: Vec<HistoricalDataPoint> = match latest_date {
+        // None when up to date, otherwise the start of the range to fetch
+        let from: Option<Option<NaiveDate>> = match latest_date {
             Some(date) => {
                 pb.set_message(format!("{} Latest: {}", 
                     style(&stock.ticker).cyan().bold(),
-                    style(&date).dim()
+                    style(date).dim()
                 ));
                 
-                let latest_naive_date: NaiveDate = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
-                let next_day: NaiveDate = latest_naive_date.succ_opt()
+                let next_day: NaiveDate = date.succ_opt()
                     .ok_or_else(|| anyhow::anyhow!("Failed to calculate next day"))?;
-                
-                let today: NaiveDate = Utc::now().date_naive();
-                
-                if next_day >= today {
-                    pb.finish_with_message(format!("{} {}", 
-                        style(&stock.ticker).cyan().bold(),
-                        style("✓ Up to date").green()
-                    ));
-                    return Ok(());
+
+                if self.options.lookback_days > 0 {
+                    // Fetch the last stored days again to pick up revised bars,
+                    // or everything when the look-back reaches past the calendar
+                    Some(date.checked_sub_days(Days::new(u64::from(self.options.lookback_days - 1))))
+                } else if next_day > latest_session {
+                    None
+                } else {
+                    Some(Some(next_day))
                 }
-                
-                let from_date: String = next_day.format("%Y-%m-%d").to_string();
-                pb.set_message(format!("{} Fetching from Yahoo Finance...", 
-                    style(&stock.ticker).cyan().bold()
-                ));
-                
-                self.yahoo_client.fetch_historical_data_from(&stock.ticker, &from_date).await?
-            }
-            None => {
-                pb.set_message(format!("{} Fetching all history...", 
-                    style(&stock.ticker).cyan().bold()
-                ));
-                
-                self.yahoo_client.fetch_all_historical_data(&stock.ticker).await?
             }
+            None => Some(None),
         };
 
-        if new_data_points.is_empty() {
-            pb.finish_with_message(format!("{} {}", 
-                style(&stock.ticker).cyan().bold(),
-                style("✓ No new data").green()
-            ));
+        let mut sync: StockSync = match from {
+            Some(from) => self.sync_range(stock, chain, from, latest_session, pb).await?,
+            None => StockSync::new(SyncOutcome::UpToDate),
+        };
+
+        if self.options.fill_gaps && latest_date.is_some() {
+            self.fill_gaps(stock, chain, &mut sync, pb).await?;
+        }
+
+        Ok(sync)
+    }
+
+    /// Fetches each range of trading days missing from the stored history of
+    /// `stock` and adds what was found to `sync`.
+    async fn fill_gaps(
+        &self,
+        stock: &Stock,
+        chain: &[Arc<dyn MarketDataProvider>],
+        sync: &mut StockSync,
+        pb: &ProgressBar,
+    ) -> Result<()> {
+        pb.set_message(format!("{} Looking for gaps...", style(&stock.ticker).cyan().bold()));
+
+        let Some(gap_list) = self.profiserve_client.get_gaps(&stock.ticker).await? else {
             return Ok(());
+        };
+
+        // Providers publish late bars, so empty gaps are only skipped for a while
+        self.empty_gaps.lock().unwrap()
+            .retain(|_, found: &mut Instant| found.elapsed() < self.options.empty_gap_retry);
+
+        for gap in &gap_list.gaps {
+            let key: (String, NaiveDate, NaiveDate) = (stock.ticker.clone(), gap.from, gap.to);
+            if self.empty_gaps.lock().unwrap().contains_key(&key) {
+                continue;
+            }
+
+            let gap_sync: StockSync = self.sync_range(stock, chain, Some(gap.from), gap.to, pb).await?;
+            if let SyncOutcome::NoNewData { exhaustive: true } = gap_sync.outcome {
+                self.empty_gaps.lock().unwrap().insert(key, Instant::now());
+            }
+
+            sync.reports.extend(gap_sync.reports);
+            sync.revisions.extend(gap_sync.revisions);
+            sync.corporate_actions += gap_sync.corporate_actions;
+            sync.failed_corporate_actions += gap_sync.failed_corporate_actions;
+            for provider in gap_sync.sources {
+                sync.add_source(provider);
+            }
+
+            match gap_sync.outcome {
+                SyncOutcome::Synchronized { created, .. } => sync.gaps_filled += created,
+                SyncOutcome::DryRun(gap_diff) => {
+                    sync.gaps_filled += gap_diff.created.len();
+
+                    // Keep the dry-run report complete
+                    match &mut sync.outcome {
+                        SyncOutcome::DryRun(diff) => {
+                            diff.created.extend(gap_diff.created);
+                            diff.splits.extend(gap_diff.splits);
+                            diff.dividends.extend(gap_diff.dividends);
+                        }
+                        outcome => *outcome = SyncOutcome::DryRun(gap_diff),
+                    }
+                }
+                _ => {}
+            }
         }
 
-        pb.set_message(format!("{} Synchronizing {} quotes...", 
+        Ok(())
+    }
+
+    /// Fetches the bars of `stock` between `from` and `to`, uploads the ones
+    /// profiserve does not have yet and overwrites the stored ones whose
+    /// values differ. Splits and dividends over the same range are
+    /// synchronized along with them, even when no provider has bars for it.
+    async fn sync_range(
+        &self,
+        stock: &Stock,
+        chain: &[Arc<dyn MarketDataProvider>],
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        pb: &ProgressBar,
+    ) -> Result<StockSync> {
+        let mut fetched: FetchedBars = match self.fetch_with_fallback(stock, chain, from, to, pb).await? {
+            Fetched::Bars(fetched) => fetched,
+            // A provider failed, its splits and dividends wait for the next pass
+            Fetched::Nothing { exhaustive: false } => return Ok(StockSync::new(SyncOutcome::NoNewData { exhaustive: false })),
+            Fetched::Nothing { exhaustive: true } => {
+                let actions: ActionsSync = self.sync_corporate_actions(stock, chain, from, to, pb).await?;
+                return Ok(self.without_new_bars(stock, actions, true));
+            }
+        };
+
+        // Some providers round the range out to whole weeks or months
+        if !fetched.provider.capabilities().ignores_range {
+            fetched.data_points.retain(|dp: &HistoricalDataPoint| {
+                from.is_none_or(|from: NaiveDate| dp.date >= from) && dp.date <= to
+            });
+        }
+        if fetched.data_points.is_empty() {
+            let actions: ActionsSync = self.sync_corporate_actions(stock, chain, from, to, pb).await?;
+            // The providers after this one were not asked
+            return Ok(self.without_new_bars(stock, actions, false));
+        }
+
+        let fetched_by: Arc<dyn MarketDataProvider> = fetched.provider.clone();
+        let provider: &'static str = fetched_by.name();
+        let reports: Vec<ReconciliationReport> = match self.options.reconcile_tolerance {
+            Some(tolerance) => self.reconcile(stock, chain, &fetched, tolerance, pb).await,
+            None => Vec::new(),
+        };
+        let new_data_points: Vec<HistoricalDataPoint> = fetched.data_points;
+
+        pb.set_message(format!("{} Comparing {} quotes...", 
             style(&stock.ticker).cyan().bold(),
             style(new_data_points.len()).yellow()
         ));
 
-        let existing_dates: HashSet<String> = self.profiserve_client.get_existing_dates(&stock.ticker).await?;
-        
-        let filtered_data_points: Vec<HistoricalDataPoint> = new_data_points.into_iter()
-            .filter(|dp: &HistoricalDataPoint| !existing_dates.contains(&dp.date))
-            .collect();
+        let earliest: NaiveDate = new_data_points.iter()
+            .map(|dp: &HistoricalDataPoint| dp.date)
+            .min()
+            .unwrap_or(to);
+        let latest: NaiveDate = new_data_points.iter()
+            .map(|dp: &HistoricalDataPoint| dp.date)
+            .max()
+            .map_or(to, |latest: NaiveDate| latest.max(to));
+        let stored: Vec<HistoricalDataPoint> = self.profiserve_client
+            .get_historical_data(&stock.ticker, earliest, latest)
+            .await?;
+        let calendar: &TradingCalendar = self.options.calendars.for_exchange(&stock.stock_exchange);
+        let mut diff: StockDiff = diff::diff(&stock.ticker, provider, &new_data_points, &stored, calendar);
 
-        if filtered_data_points.is_empty() {
-            pb.finish_with_message(format!("{} {}", 
-                style(&stock.ticker).cyan().bold(),
-                style("✓ All data already exists").green()
-            ));
-            return Ok(());
+        let ActionsSync { recorded: actions, failed: failed_corporate_actions } =
+            self.sync_corporate_actions(stock, chain, from, to, pb).await?;
+        let corporate_actions: usize = actions.len();
+
+        if self.options.dry_run {
+            diff.splits = actions.splits;
+            diff.dividends = actions.dividends;
+
+            return Ok(StockSync {
+                outcome: SyncOutcome::DryRun(diff),
+                reports,
+                revisions: Vec::new(),
+                gaps_filled: 0,
+                corporate_actions,
+                failed_corporate_actions,
+                sources: Vec::new(),
+            });
+        }
+
+        if diff.is_empty() {
+            return Ok(StockSync {
+                outcome: SyncOutcome::AlreadyStored,
+                reports,
+                revisions: Vec::new(),
+                gaps_filled: 0,
+                corporate_actions,
+                failed_corporate_actions,
+                sources: vec![fetched_by],
+            });
         }
 
         pb.set_message(format!("{} Uploading {} new quotes...", 
             style(&stock.ticker).cyan().bold(),
-            style(filtered_data_points.len()).yellow()
+            style(diff.created.len()).yellow()
         ));
 
-        let mut success_count = 0;
-        for data_point in filtered_data_points {
-            match self.profiserve_client.create_historical_data_point(&stock.ticker, &data_point).await {
-                Ok(_) => {
-                    success_count += 1;
+        let mut success_count: usize = 0;
+        let mut failed_batches: usize = 0;
+        for batch in diff.created.chunks(self.options.batch_size) {
+            match self.profiserve_client.create_historical_data_batch(&stock.ticker, batch).await {
+                Ok(report) => {
+                    success_count += report.created;
+
+                    for result in report.results.iter().filter(|r: &&BatchItemResult| {
+                        matches!(r.status, BatchItemStatus::Conflicted | BatchItemStatus::Invalid)
+                    }) {
+                        pb.println(format!("    {} Rejected {}: {:?}{}", 
+                            style("⚠").yellow(),
+                            style(result.date.map_or_else(|| "?".to_string(), |date: NaiveDate| date.to_string())).dim(),
+                            result.status,
+                            result.reason.as_ref().map(|reason: &String| format!(" ({})", reason)).unwrap_or_default()
+                        ));
+                    }
+                }
+                Err(e) => {
+                    failed_batches += 1;
+                    pb.println(format!("    {} Failed to upload {} quotes from {}: {}", 
+                        style("⚠").yellow(),
+                        batch.len(),
+                        style(batch[0].date).dim(),
+                        style(format!("{}", e)).dim()
+                    ));
                 }
+            }
+        }
+
+        if !diff.changed.is_empty() {
+            pb.set_message(format!("{} Revising {} quotes...", 
+                style(&stock.ticker).cyan().bold(),
+                style(diff.changed.len()).yellow()
+            ));
+        }
+
+        let mut revisions: Vec<ChangedBar> = Vec::new();
+        let mut failed_revisions: usize = 0;
+        for changed in diff.changed {
+            match self.profiserve_client.update_historical_data_point(&stock.ticker, &changed.fetched).await {
+                Ok(()) => revisions.push(changed),
                 Err(e) => {
-                    pb.println(format!("    {} Failed to upload {}: {}", 
+                    failed_revisions += 1;
+                    pb.println(format!("    {} Failed to revise {}: {}", 
                         style("⚠").yellow(),
-                        style(&data_point.date).dim(),
+                        style(changed.fetched.date).dim(),
                         style(format!("{}", e)).dim()
                     ));
                 }
             }
         }
 
-        pb.finish_with_message(format!("{} {} {}", 
+        if failed_batches > 0 || failed_revisions > 0 {
+            return Err(anyhow::anyhow!(
+                "{} upload batches and {} revisions failed after synchronizing {} quotes",
+                failed_batches,
+                failed_revisions,
+                success_count
+            ));
+        }
+
+        Ok(StockSync {
+            outcome: SyncOutcome::Synchronized {
+                provider,
+                created: success_count,
+                revised: revisions.len(),
+            },
+            reports,
+            revisions,
+            gaps_filled: 0,
+            corporate_actions,
+            failed_corporate_actions,
+            sources: vec![fetched_by],
+        })
+    }
+
+    /// Outcome of a range no provider had bars for, where splits and
+    /// dividends were still recorded, or would be on a dry run.
+    fn without_new_bars(&self, stock: &Stock, actions: ActionsSync, exhaustive: bool) -> StockSync {
+        let ActionsSync { recorded: actions, failed: failed_corporate_actions } = actions;
+        let corporate_actions: usize = actions.len();
+        let outcome: SyncOutcome = if self.options.dry_run && corporate_actions > 0 {
+            SyncOutcome::DryRun(StockDiff {
+                ticker: stock.ticker.clone(),
+                splits: actions.splits,
+                dividends: actions.dividends,
+                ..StockDiff::default()
+            })
+        } else {
+            SyncOutcome::NoNewData { exhaustive }
+        };
+
+        StockSync {
+            corporate_actions,
+            failed_corporate_actions,
+            ..StockSync::new(outcome)
+        }
+    }
+
+    /// Fetches the splits and dividends of `stock` between `from` and `to`
+    /// from the first provider of the chain that has them, and records the
+    /// ones profiserve is missing or holds with other values. Returns those
+    /// that were recorded, which are only reported on a dry run. A provider
+    /// failing or profiserve refusing one is only warned about as bars
+    /// matter more.
+    async fn sync_corporate_actions(
+        &self,
+        stock: &Stock,
+        chain: &[Arc<dyn MarketDataProvider>],
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        pb: &ProgressBar,
+    ) -> Result<ActionsSync> {
+        let Some(provider) = chain.iter().find(|provider| provider.capabilities().corporate_actions) else {
+            return Ok(ActionsSync::default());
+        };
+
+        pb.set_message(format!("{} Fetching corporate actions from {}...", 
             style(&stock.ticker).cyan().bold(),
-            style("✓").green().bold(),
-            style(format!("Synchronized {} quotes", success_count)).green()
+            provider.name()
         ));
 
+        let fetched: CorporateActions = match provider.fetch_corporate_actions(stock, from, to).await {
+            Ok(Some(fetched)) if !fetched.is_empty() => fetched,
+            Ok(_) => return Ok(ActionsSync::default()),
+            Err(e) => {
+                pb.println(format!("    {} Failed to fetch corporate actions from {}: {}", 
+                    style("⚠").yellow(),
+                    provider.name(),
+                    style(format!("{}", e)).dim()
+                ));
+                return Ok(ActionsSync::default());
+            }
+        };
+
+        let in_range = |date: NaiveDate| from.is_none_or(|from: NaiveDate| date >= from) && date <= to;
+        let stored_splits: Vec<Split> = self.profiserve_client.get_splits(&stock.ticker, from, to).await?;
+        let stored_dividends: Vec<Dividend> = self.profiserve_client.get_dividends(&stock.ticker, from, to).await?;
+
+        let splits: Vec<(Split, bool)> = pending(fetched.splits, &stored_splits, |split: &Split| split.date)
+            .into_iter()
+            .filter(|(split, _): &(Split, bool)| in_range(split.date))
+            .collect();
+        let dividends: Vec<(Dividend, bool)> = pending(fetched.dividends, &stored_dividends, |dividend: &Dividend| dividend.date)
+            .into_iter()
+            .filter(|(dividend, _): &(Dividend, bool)| in_range(dividend.date))
+            .collect();
+
+        let mut sync: ActionsSync = ActionsSync::default();
+        let failed = |kind: &str, date: NaiveDate, e: anyhow::Error| {
+            pb.println(format!("    {} Failed to record {} on {}: {}", 
+                style("⚠").yellow(),
+                kind,
+                style(date).dim(),
+                style(format!("{}", e)).dim()
+            ));
+        };
+
+        for (split, stored) in splits {
+            let written: Result<()> = match (self.options.dry_run, stored) {
+                (true, _) => Ok(()),
+                (false, true) => self.profiserve_client.update_split(&stock.ticker, &split).await,
+                (false, false) => self.profiserve_client.create_split(&stock.ticker, &split).await,
+            };
+            match written {
+                Ok(()) => sync.recorded.splits.push(split),
+                Err(e) => {
+                    sync.failed += 1;
+                    failed("split", split.date, e);
+                }
+            }
+        }
+        for (dividend, stored) in dividends {
+            let written: Result<()> = match (self.options.dry_run, stored) {
+                (true, _) => Ok(()),
+                (false, true) => self.profiserve_client.update_dividend(&stock.ticker, &dividend).await,
+                (false, false) => self.profiserve_client.create_dividend(&stock.ticker, &dividend).await,
+            };
+            match written {
+                Ok(()) => sync.recorded.dividends.push(dividend),
+                Err(e) => {
+                    sync.failed += 1;
+                    failed("dividend", dividend.date, e);
+                }
+            }
+        }
+
+        Ok(sync)
+    }
+
+    /// Asks each provider of the chain in turn for the bars of `stock`,
+    /// falling through to the next one when a provider fails or has nothing.
+    /// Returns [`Fetched::Nothing`] when no provider had any bar.
+    async fn fetch_with_fallback(
+        &self,
+        stock: &Stock,
+        chain: &[Arc<dyn MarketDataProvider>],
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        pb: &ProgressBar,
+    ) -> Result<Fetched> {
+        let mut failures: Vec<String> = Vec::new();
+
+        for (position, provider) in chain.iter().enumerate() {
+            if from.is_none() && !provider.capabilities().full_history {
+                pb.println(format!("    {} {} only serves recent history for {}", 
+                    style("⚠").yellow(),
+                    provider.name(),
+                    style(&stock.ticker).dim()
+                ));
+            }
+
+            pb.set_message(format!("{} Fetching {} from {}...", 
+                style(&stock.ticker).cyan().bold(),
+                if from.is_some() { "new quotes" } else { "all history" },
+                provider.name()
+            ));
+
+            match provider.fetch_daily_bars(stock, from, to).await {
+                Ok(data_points) if !data_points.is_empty() => {
+                    return Ok(Fetched::Bars(FetchedBars {
+                        provider: provider.clone(),
+                        position,
+                        data_points,
+                    }));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if position + 1 < chain.len() {
+                        pb.println(format!("    {} {} failed, falling back to {}: {}", 
+                            style("⚠").yellow(),
+                            provider.name(),
+                            chain[position + 1].name(),
+                            style(format!("{}", e)).dim()
+                        ));
+                    }
+                    failures.push(format!("{}: {}", provider.name(), e));
+                }
+            }
+        }
+
+        // Only report an error when no provider could answer at all, an empty
+        // answer simply means there is nothing new
+        if !failures.is_empty() && failures.len() == chain.len() {
+            return Err(anyhow::anyhow!("All providers failed ({})", failures.join("; ")));
+        }
+
+        Ok(Fetched::Nothing { exhaustive: failures.is_empty() })
+    }
+
+    /// Fetches the range of `fetched` from the providers after the one that
+    /// supplied it and compares their closes.
+    async fn reconcile(
+        &self,
+        stock: &Stock,
+        chain: &[Arc<dyn MarketDataProvider>],
+        fetched: &FetchedBars,
+        tolerance: f64,
+        pb: &ProgressBar,
+    ) -> Vec<ReconciliationReport> {
+        let (Some(from), Some(to)) = (
+            fetched.data_points.iter().map(|dp: &HistoricalDataPoint| dp.date).min(),
+            fetched.data_points.iter().map(|dp: &HistoricalDataPoint| dp.date).max(),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut reports: Vec<ReconciliationReport> = Vec::new();
+
+        for secondary in &chain[fetched.position + 1..] {
+            pb.set_message(format!("{} Reconciling with {}...", 
+                style(&stock.ticker).cyan().bold(),
+                secondary.name()
+            ));
+
+            match secondary.fetch_daily_bars(stock, Some(from), to).await {
+                Ok(secondary_bars) => reports.push(reconciliation::reconcile(
+                    &stock.ticker,
+                    (fetched.provider.name(), &fetched.data_points),
+                    (secondary.name(), &secondary_bars),
+                    tolerance,
+                )),
+                Err(e) => {
+                    pb.println(format!("    {} Could not reconcile with {}: {}", 
+                        style("⚠").yellow(),
+                        secondary.name(),
+                        style(format!("{}", e)).dim()
+                    ));
+                }
+            }
+        }
+
+        reports
+    }
+
+    /// Fills the metadata fields the stock is missing from the provider and
+    /// saves them to profiserve, leaving the stock alone when nothing changed.
+    async fn enrich_stock(&self, stock: &Stock, chain: &[Arc<dyn MarketDataProvider>]) -> Result<()> {
+        let Some(provider) = chain.iter().find(|provider| provider.capabilities().metadata) else {
+            return Ok(());
+        };
+
+        let Some(merged) = provider.fetch_metadata(stock).await? else {
+            return Ok(());
+        };
+
+        if merged == *stock {
+            return Ok(());
+        }
+
+        self.profiserve_client.update_stock(&stock.ticker, &merged).await?;
         Ok(())
     }
 }
+
+/// The `fetched` corporate actions profiserve is missing or holds with other
+/// values, each with whether one is stored for its date.
+fn pending<T: PartialEq>(fetched: Vec<T>, stored: &[T], date: impl Fn(&T) -> NaiveDate) -> Vec<(T, bool)> {
+    fetched.into_iter()
+        .filter_map(|action: T| match stored.iter().find(|stored: &&T| date(stored) == date(&action)) {
+            None => Some((action, false)),
+            Some(stored) if *stored != action => Some((action, true)),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+/// Number of sessions that closed after `latest` without a bar being stored.
+fn sessions_behind(calendar: &TradingCalendar, latest: NaiveDate) -> usize {
+    let latest_session: NaiveDate = calendar.latest_session(Utc::now());
+
+    match latest.succ_opt() {
+        Some(next_day) => calendar.trading_days(next_day, latest_session).len(),
+        None => 0,
+    }
+}
+
+/// Counts the stocks of a pass in each state.
+fn summarize(results: &[(usize, Result<StockSync>)]) -> SyncSummary {
+    let mut summary: SyncSummary = SyncSummary::default();
+
+    for (_, result) in results {
+        match result {
+            Ok(sync) if sync.changes_data() => summary.synchronized += 1,
+            Ok(_) => summary.unchanged += 1,
+            Err(_) => summary.failed += 1,
+        }
+    }
+
+    summary
+}
+
+/// Prints one line per stock, in the order of `stocks`, then the totals.
+fn print_summary(
+    stocks: &[Stock],
+    results: &[(usize, Result<StockSync>)],
+    summary: SyncSummary,
+    dry_run: bool,
+    elapsed: Duration,
+) {
+    for (index, result) in results {
+        let ticker = style(format!("{:<10}", stocks[*index].ticker)).cyan().bold();
+
+        match result {
+            Ok(sync) => {
+                let message: String = match &sync.outcome {
+                    SyncOutcome::UpToDate => "Up to date".to_string(),
+                    SyncOutcome::NoNewData { .. } => "No new data".to_string(),
+                    SyncOutcome::AlreadyStored => "All data already exists".to_string(),
+                    SyncOutcome::Synchronized { provider, created, revised: 0 } => {
+                        format!("Synchronized {} quotes from {}", created, provider)
+                    }
+                    SyncOutcome::Synchronized { provider, created, revised } => {
+                        format!("Synchronized {} quotes and revised {} from {}", created, revised, provider)
+                    }
+                    SyncOutcome::DryRun(diff) => format!("Would create {} and change {} quotes from {}", 
+                        diff.created.len(),
+                        diff.changed.len(),
+                        diff.provider.unwrap_or("no provider")
+                    ),
+                };
+
+                let message: String = match sync.gaps_filled {
+                    0 => message,
+                    filled => format!("{}, {} {} quotes in gaps", message, if dry_run { "would fill" } else { "filled" }, filled),
+                };
+                let message: String = match sync.corporate_actions {
+                    0 => message,
+                    recorded => format!("{}, {} {} corporate actions", message, if dry_run { "would record" } else { "recorded" }, recorded),
+                };
+                let message: String = match sync.failed_corporate_actions {
+                    0 => message,
+                    failed => format!("{}, {} corporate actions failed", message, failed),
+                };
+
+                println!("  {} {} {}", style("✓").green().bold(), ticker, style(message).green());
+            }
+            Err(e) => {
+                println!("  {} {} {}", style("✗").red().bold(), ticker, style(format!("{}", e)).dim());
+            }
+        }
+    }
+
+    println!("\n{} {} {}, {} unchanged, {} failed in {:.1}s", 
+        style("📊").bold(),
+        style(summary.synchronized).green().bold(),
+        if dry_run { "would change" } else { "synchronized" },
+        style(summary.unchanged).cyan(),
+        if summary.failed > 0 { style(summary.failed).red().bold() } else { style(summary.failed).dim() },
+        elapsed.as_secs_f64()
+    );
+}
+
+/// Prints the gap analysis of a stock below its status line.
+fn print_gaps(gap_list: &HistoryGapList) {
+    if gap_list.gaps.is_empty() {
+        println!("      {} {}", 
+            style("✓").green(),
+            style(format!("No gaps, {} trading days stored", gap_list.stored)).dim()
+        );
+        return;
+    }
+
+    println!("      {} {} gaps, {} of {} trading days missing", 
+        style("⚠").yellow(),
+        style(gap_list.gaps.len()).yellow().bold(),
+        style(gap_list.expected - gap_list.stored).red(),
+        gap_list.expected
+    );
+
+    for gap in gap_list.gaps.iter().take(DIFF_LINES) {
+        println!("        {} {} to {} {}", 
+            style("…").red().bold(),
+            style(gap.from).dim(),
+            style(gap.to).dim(),
+            style(format!("(trading days missing: {})", gap.missing)).red()
+        );
+    }
+    if gap_list.gaps.len() > DIFF_LINES {
+        println!("        {}", style(format!("… and {} more", gap_list.gaps.len() - DIFF_LINES)).dim());
+    }
+}
+
+fn print_revisions(revisions: &[(&str, &ChangedBar)]) {
+    println!("\n{} Revised {} stored quotes", 
+        style("✎").bold(),
+        style(revisions.len()).yellow().bold()
+    );
+
+    for (ticker, bar) in revisions {
+        println!("  {} {} {}", 
+            style(format!("{:<10}", ticker)).cyan().bold(),
+            style(bar.fetched.date).dim(),
+            changed_fields(bar)
+        );
+    }
+}
+
+/// Number of lines printed per section of a stock's diff before eliding.
+const DIFF_LINES: usize = 10;
+
+fn print_diffs(diffs: &[&StockDiff]) {
+    let created: usize = diffs.iter().map(|diff: &&StockDiff| diff.created.len()).sum();
+    let changed: usize = diffs.iter().map(|diff: &&StockDiff| diff.changed.len()).sum();
+
+    println!("\n{} Dry run: would create {} and change {} quotes, nothing was written", 
+        style("🔍").bold(),
+        style(created).green().bold(),
+        style(changed).yellow().bold()
+    );
+
+    for diff in diffs.iter().filter(|diff: &&&StockDiff| !diff.is_empty() || !diff.gaps.is_empty()) {
+        println!("\n  {} {}", 
+            style(&diff.ticker).cyan().bold(),
+            style(format!("from {}", diff.provider.unwrap_or("no provider"))).dim()
+        );
+
+        for bar in diff.created.iter().take(DIFF_LINES) {
+            println!("    {} {} {:>12.4} {:>12.4} {:>12.4} {:>12.4} {:>12}", 
+                style("+").green().bold(),
+                style(bar.date).dim(),
+                bar.open,
+                bar.high,
+                bar.low,
+                bar.close,
+                bar.volume
+            );
+        }
+        print_elided(diff.created.len());
+
+        for bar in diff.changed.iter().take(DIFF_LINES) {
+            println!("    {} {} {}", 
+                style("~").yellow().bold(),
+                style(bar.fetched.date).dim(),
+                changed_fields(bar)
+            );
+        }
+        print_elided(diff.changed.len());
+
+        for gap in diff.gaps.iter().take(DIFF_LINES) {
+            println!("    {} {} to {} {}", 
+                style("…").red().bold(),
+                style(gap.from).dim(),
+                style(gap.to).dim(),
+                style(format!("(trading days missing: {})", gap.missing)).red()
+            );
+        }
+        print_elided(diff.gaps.len());
+    }
+}
+
+fn print_elided(count: usize) {
+    if count > DIFF_LINES {
+        println!("    {}", style(format!("… and {} more", count - DIFF_LINES)).dim());
+    }
+}
+
+/// Describes the fields of a changed bar that differ, such as `close 1.5 → 1.6`.
+fn changed_fields(bar: &ChangedBar) -> String {
+    let (stored, fetched) = (&bar.stored, &bar.fetched);
+    let mut fields: Vec<String> = Vec::new();
+
+    for (name, stored_value, fetched_value) in [
+        ("open", stored.open, fetched.open),
+        ("high", stored.high, fetched.high),
+        ("low", stored.low, fetched.low),
+        ("close", stored.close, fetched.close),
+    ] {
+        if stored_value != fetched_value {
+            fields.push(format!("{} {} → {}", name, stored_value, fetched_value));
+        }
+    }
+    if stored.volume != fetched.volume {
+        fields.push(format!("volume {} → {}", stored.volume, fetched.volume));
+    }
+
+    fields.join(", ")
+}
+
+/// Prints the dry-run report of every stock as a single JSON document, with
+/// stocks that had nothing to compare or failed included.
+fn print_json_report(stocks: &[Stock], results: &[(usize, Result<StockSync>)], summary: SyncSummary) {
+    #[derive(Serialize)]
+    struct JsonReport<'a> {
+        summary: SyncSummary,
+        stocks: Vec<&'a StockDiff>,
+    }
+
+    let placeholders: Vec<StockDiff> = results.iter()
+        .map(|(index, result): &(usize, Result<StockSync>)| StockDiff {
+            ticker: stocks[*index].ticker.clone(),
+            error: result.as_ref().err().map(|e: &anyhow::Error| e.to_string()),
+            ..StockDiff::default()
+        })
+        .collect();
+
+    let report = JsonReport {
+        summary,
+        stocks: results.iter()
+            .zip(&placeholders)
+            .map(|((_, result), placeholder): (&(usize, Result<StockSync>), &StockDiff)| match result {
+                Ok(StockSync { outcome: SyncOutcome::DryRun(diff), .. }) => diff,
+                _ => placeholder,
+            })
+            .collect(),
+    };
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("{} Failed to serialize the dry-run report: {}", style("✗").red().bold(), e),
+    }
+}
+
+fn print_reconciliation(reports: &[&ReconciliationReport]) {
+    let compared: usize = reports.iter().map(|report: &&ReconciliationReport| report.compared).sum();
+    let mismatched: Vec<&ReconciliationReport> = reports.iter()
+        .copied()
+        .filter(|report: &&ReconciliationReport| !report.mismatches.is_empty())
+        .collect();
+
+    println!("\n{} Reconciled {} overlapping quotes across {} comparisons", 
+        style("🔍").bold(),
+        style(compared).cyan().bold(),
+        style(reports.len()).cyan()
+    );
+
+    if mismatched.is_empty() {
+        println!("  {} {}", style("✓").green().bold(), style("All providers agree").green());
+        return;
+    }
+
+    for report in mismatched {
+        println!("  {} {} {} vs {}: {} of {} closes differ", 
+            style("⚠").yellow().bold(),
+            style(&report.ticker).yellow().bold(),
+            report.primary,
+            report.secondary,
+            style(report.mismatches.len()).red(),
+            report.compared
+        );
+
+        for mismatch in report.mismatches.iter().take(DIFF_LINES) {
+            println!("      {} {:>12.4} {:>12.4} {}", 
+                style(mismatch.date).dim(),
+                mismatch.primary_close,
+                mismatch.secondary_close,
+                style(format!("({:+.2}%)", mismatch.relative_difference * 100.0)).red()
+            );
+        }
+        if report.mismatches.len() > DIFF_LINES {
+            println!("      {}", style(format!("… and {} more", report.mismatches.len() - DIFF_LINES)).dim());
+        }
+    }
+}
@@ -1,103 +1,370 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
 use anyhow::Result;
-use chrono::{DateTime, Utc};
-use crate::models::{HistoricalDataPoint, YahooFinanceResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use proficalendar::Calendars;
+use crate::market_data::{MarketDataProvider, ProviderCapabilities};
+use crate::models::{
+    AssetClass, ChartDividend, ChartError, ChartEvents, ChartMeta, ChartResult, ChartSplit, CorporateActions, Dividend,
+    HistoricalDataPoint, Split, Stock, YahooFinanceResponse,
+};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::retry::RetryPolicy;
 
 pub struct YahooFinanceClient {
+    base_url: String,
     client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    rate_limiter: RateLimiter,
+    /// Exchange time zones used when a chart does not name its own.
+    calendars: Calendars,
+    /// Splits and dividends that came with the last bars fetched for each
+    /// ticker, handed out instead of requesting the same chart again.
+    events: Mutex<HashMap<String, FetchedEvents>>,
+}
+
+/// Splits and dividends of a chart, and the days it was requested for.
+struct FetchedEvents {
+    from: Option<NaiveDate>,
+    to: NaiveDate,
+    actions: CorporateActions,
+}
+
+impl FetchedEvents {
+    /// Whether the chart was requested for every day between `from` and `to`.
+    fn covers(&self, from: Option<NaiveDate>, to: NaiveDate) -> bool {
+        let starts_before: bool = match (self.from, from) {
+            (None, _) => true,
+            (Some(fetched_from), Some(from)) => fetched_from <= from,
+            (Some(_), None) => false,
+        };
+
+        starts_before && to <= self.to
+    }
+
+    fn within(self, from: Option<NaiveDate>, to: NaiveDate) -> CorporateActions {
+        let in_range = |date: NaiveDate| from.is_none_or(|from: NaiveDate| date >= from) && date <= to;
+
+        CorporateActions {
+            splits: self.actions.splits.into_iter().filter(|split: &Split| in_range(split.date)).collect(),
+            dividends: self.actions.dividends.into_iter().filter(|dividend: &Dividend| in_range(dividend.date)).collect(),
+        }
+    }
+}
+
+/// Time zone a chart's bars are dated in.
+enum ExchangeTimezone {
+    Named(Tz),
+    /// Yahoo's `gmtoffset`, which is only right outside of daylight saving
+    /// changes, as a last resort.
+    Offset(FixedOffset),
+}
+
+impl ExchangeTimezone {
+    fn date(&self, at: DateTime<Utc>) -> NaiveDate {
+        match self {
+            ExchangeTimezone::Named(timezone) => at.with_timezone(timezone).date_naive(),
+            ExchangeTimezone::Offset(offset) => at.with_timezone(offset).date_naive(),
+        }
+    }
 }
 
 impl YahooFinanceClient {
-    pub fn new() -> Self {
+    pub fn new(base_url: String, retry_policy: RetryPolicy, rate_limiter: RateLimiter) -> Self {
         Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
             client: reqwest::Client::new(),
+            retry_policy,
+            rate_limiter,
+            calendars: Calendars::builtin(),
+            events: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Uses `calendars` instead of the built-in ones for the time zones of
+    /// exchanges.
+    pub fn with_calendars(mut self, calendars: Calendars) -> Self {
+        self.calendars = calendars;
+        self
+    }
+
+    /// Fetches the daily bars of `ticker`, listed on `exchange`, between two
+    /// Unix timestamps, along with the splits and dividends Yahoo sends in the
+    /// same chart. Bars and events are dated in the exchange's time zone as
+    /// Yahoo stamps some markets' bars with their local midnight, which falls
+    /// on the previous day in UTC.
     pub async fn fetch_historical_data(
         &self,
         ticker: &str,
+        exchange: &str,
         period1: i64,
         period2: i64,
-    ) -> Result<Vec<HistoricalDataPoint>> {
-        let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{}?period1={}&period2={}&interval=1d",
-            ticker, period1, period2
-        );
-        
-        let response = self.client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0")
-            .send()
-            .await?;
+    ) -> Result<(Vec<HistoricalDataPoint>, CorporateActions)> {
+        let query = format!("period1={}&period2={}&interval=1d&events=div,splits", period1, period2);
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Yahoo Finance API error: {}",
-                response.status()
-            ));
-        }
+        let Some(result) = self.fetch_chart(ticker, &query).await? else {
+            return Ok((Vec::new(), CorporateActions::default()));
+        };
 
-        let yahoo_response: YahooFinanceResponse = response.json().await?;
-        
-        if yahoo_response.chart.result.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let result = &yahoo_response.chart.result[0];
+        let timezone: ExchangeTimezone = self.exchange_timezone(result.meta.as_ref(), exchange);
+        let actions: CorporateActions = parse_events(result.events.unwrap_or_default(), &timezone)?;
         let timestamps = &result.timestamp;
-        let quote = &result.indicators.quote[0];
+        let Some(quote) = result.indicators.quote.first() else {
+            return Ok((Vec::new(), actions));
+        };
+
+        let mut data_points: Vec<HistoricalDataPoint> = Vec::new();
 
-        let mut data_points = Vec::new();
+        // Yahoo sends nulls for days it has no complete bar for, those are skipped
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            let field = |values: &[Option<f64>]| values.get(i).copied().flatten();
 
-        for i in 0..timestamps.len() {
             if let (Some(open), Some(high), Some(low), Some(close), Some(volume)) = (
-                quote.open[i],
-                quote.high[i],
-                quote.low[i],
-                quote.close[i],
-                quote.volume[i],
+                field(&quote.open),
+                field(&quote.high),
+                field(&quote.low),
+                field(&quote.close),
+                quote.volume.get(i).copied().flatten(),
             ) {
-                let dt = DateTime::from_timestamp(timestamps[i], 0)
+                let dt = DateTime::from_timestamp(*timestamp, 0)
                     .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))?;
-                let date = dt.format("%Y-%m-%d").to_string();
-
-                data_points.push(HistoricalDataPoint {
-                    date,
+                let data_point = HistoricalDataPoint {
+                    date: timezone.date(dt),
                     open,
                     high,
                     low,
                     close,
                     volume,
-                });
+                };
+
+                // During a session Yahoo may append a live bar for a day it
+                // already has one for, the latest one wins
+                match data_points.last_mut() {
+                    Some(last) if last.date == data_point.date => *last = data_point,
+                    _ => data_points.push(data_point),
+                }
             }
         }
 
-        Ok(data_points)
+        Ok((data_points, actions))
+    }
+
+    /// Yahoo's range for the days between `from` and `to`, a half-open pair
+    /// of timestamps taken at midnight on the exchange so that its first and
+    /// last days are included.
+    fn period(&self, stock: &Stock, from: Option<NaiveDate>, to: NaiveDate) -> Result<(i64, i64)> {
+        let timezone: Tz = self.calendars.for_exchange(&stock.stock_exchange).timezone;
+        let period1 = match from {
+            Some(from) => midnight(from, timezone)?,
+            None => 0,
+        };
+        let period2 = match to.succ_opt() {
+            Some(day_after) => midnight(day_after, timezone)?.min(Utc::now().timestamp()),
+            None => Utc::now().timestamp(),
+        };
+
+        Ok((period1, period2))
+    }
+
+    /// Picks the time zone of a chart: the one Yahoo names, else the one of
+    /// the exchange's calendar, else Yahoo's current offset, else UTC.
+    fn exchange_timezone(&self, meta: Option<&ChartMeta>, exchange: &str) -> ExchangeTimezone {
+        let named: Option<Tz> = meta
+            .and_then(|meta: &ChartMeta| meta.exchange_timezone_name.as_deref())
+            .and_then(|name: &str| name.parse::<Tz>().ok())
+            .or_else(|| self.calendars.get(exchange).map(|calendar| calendar.timezone));
+        if let Some(timezone) = named {
+            return ExchangeTimezone::Named(timezone);
+        }
+
+        meta.and_then(|meta: &ChartMeta| meta.gmtoffset)
+            .and_then(FixedOffset::east_opt)
+            .map_or(ExchangeTimezone::Named(Tz::UTC), ExchangeTimezone::Offset)
+    }
+
+    /// Fetches the chart `meta` block for `ticker` using the smallest chart Yahoo serves.
+    pub async fn fetch_chart_meta(&self, ticker: &str) -> Result<Option<ChartMeta>> {
+        let result = self.fetch_chart(ticker, "range=1d&interval=1d").await?;
+
+        Ok(result.and_then(|result: ChartResult| result.meta))
     }
 
-    pub async fn fetch_historical_data_from(
+    /// Requests a chart and returns its first result. Errors reported by
+    /// Yahoo in the chart body are turned into errors.
+    async fn fetch_chart(&self, ticker: &str, query: &str) -> Result<Option<ChartResult>> {
+        let url = format!("{}/v8/finance/chart/{}?{}", self.base_url, ticker, query);
+
+        let request = self.client
+            .get(&url)
+            .header("User-Agent", "Mozilla/5.0");
+
+        let response = self.retry_policy
+            .send_limited(request, &self.rate_limiter)
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let description = response.json::<YahooFinanceResponse>().await
+                .ok()
+                .and_then(|body: YahooFinanceResponse| body.chart.error)
+                .map(|error: ChartError| format!(" - {}", error.description))
+                .unwrap_or_default();
+
+            return Err(anyhow::anyhow!(
+                "Yahoo Finance API error: {}{}",
+                status,
+                description
+            ));
+        }
+
+        let yahoo_response: YahooFinanceResponse = response.json().await?;
+
+        if let Some(error) = yahoo_response.chart.error {
+            return Err(anyhow::anyhow!(
+                "Yahoo Finance API error: {} - {}",
+                error.code,
+                error.description
+            ));
+        }
+
+        Ok(yahoo_response.chart.result.unwrap_or_default().into_iter().next())
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for YahooFinanceClient {
+    fn name(&self) -> &'static str {
+        "yahoo"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            full_history: true,
+            metadata: true,
+            corporate_actions: true,
+            ignores_range: false,
+        }
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(self.rate_limiter.limit())
+    }
+
+    async fn fetch_daily_bars(
         &self,
-        ticker: &str,
-        from_date: &str,
+        stock: &Stock,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
     ) -> Result<Vec<HistoricalDataPoint>> {
-        let from_datetime = chrono::NaiveDate::parse_from_str(from_date, "%Y-%m-%d")?
-            .and_hms_opt(0, 0, 0)
-            .ok_or_else(|| anyhow::anyhow!("Invalid time"))?
-            .and_utc();
-        
-        let period1 = from_datetime.timestamp();
-        let period2 = Utc::now().timestamp();
+        let (period1, period2) = self.period(stock, from, to)?;
+
+        let (data_points, actions) = self.fetch_historical_data(&stock.ticker, &stock.stock_exchange, period1, period2).await?;
+        self.events.lock().unwrap().insert(stock.ticker.clone(), FetchedEvents { from, to, actions });
 
-        self.fetch_historical_data(ticker, period1, period2).await
+        Ok(data_points)
     }
 
-    pub async fn fetch_all_historical_data(
+    async fn fetch_corporate_actions(
         &self,
-        ticker: &str,
-    ) -> Result<Vec<HistoricalDataPoint>> {
-        let period1 = 0;
-        let period2 = Utc::now().timestamp();
+        stock: &Stock,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+    ) -> Result<Option<CorporateActions>> {
+        let fetched: Option<FetchedEvents> = self.events.lock().unwrap().remove(&stock.ticker);
+        if let Some(fetched) = fetched.filter(|fetched: &FetchedEvents| fetched.covers(from, to)) {
+            return Ok(Some(fetched.within(from, to)));
+        }
+
+        let (period1, period2) = self.period(stock, from, to)?;
+
+        let (_, actions) = self.fetch_historical_data(&stock.ticker, &stock.stock_exchange, period1, period2).await?;
+        Ok(Some(actions))
+    }
+
+    async fn fetch_metadata(&self, stock: &Stock) -> Result<Option<Stock>> {
+        let meta = self.fetch_chart_meta(&stock.ticker).await?;
+
+        Ok(meta.map(|meta: ChartMeta| {
+            let timezone: ExchangeTimezone = self.exchange_timezone(Some(&meta), &stock.stock_exchange);
+            merge_metadata(stock, &meta, &timezone)
+        }))
+    }
+}
+
+/// Start of `date` in `timezone`, or the first instant of it where a
+/// daylight saving change skips midnight.
+fn midnight(date: NaiveDate, timezone: Tz) -> Result<i64> {
+    let local = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid time"))?;
+
+    let datetime = timezone.from_local_datetime(&local).earliest()
+        .or_else(|| timezone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+        .ok_or_else(|| anyhow::anyhow!("Invalid time"))?;
+
+    Ok(datetime.timestamp())
+}
+
+/// Dates and sorts the splits and dividends of a chart.
+fn parse_events(events: ChartEvents, timezone: &ExchangeTimezone) -> Result<CorporateActions> {
+    let date = |timestamp: i64| DateTime::from_timestamp(timestamp, 0)
+        .map(|dt: DateTime<Utc>| timezone.date(dt))
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"));
+
+    let mut splits: Vec<Split> = events.splits.values()
+        .map(|split: &ChartSplit| Ok(Split {
+            date: date(split.date)?,
+            numerator: split.numerator,
+            denominator: split.denominator,
+        }))
+        .collect::<Result<Vec<Split>>>()?;
+    let mut dividends: Vec<Dividend> = events.dividends.values()
+        .map(|dividend: &ChartDividend| Ok(Dividend {
+            date: date(dividend.date)?,
+            amount: dividend.amount,
+        }))
+        .collect::<Result<Vec<Dividend>>>()?;
+
+    splits.sort_by_key(|split: &Split| split.date);
+    dividends.sort_by_key(|dividend: &Dividend| dividend.date);
+
+    Ok(CorporateActions { splits, dividends })
+}
+
+/// Returns a copy of `stock` where the fields it is missing are filled from
+/// Yahoo's chart metadata. Fields that are already set are never overwritten.
+/// The listing date is taken in the exchange's time zone, like bars are.
+fn merge_metadata(stock: &Stock, meta: &ChartMeta, timezone: &ExchangeTimezone) -> Stock {
+    let mut merged: Stock = stock.clone();
+
+    if merged.name.is_none() {
+        merged.name = meta.long_name.clone().or_else(|| meta.short_name.clone());
+    }
+    if merged.currency.is_none() {
+        // Minor units such as `GBp` for pence only differ from their currency by case
+        merged.currency = meta.currency.clone();
+    }
+    if merged.asset_class.is_none() {
+        merged.asset_class = meta.instrument_type.as_deref().and_then(asset_class);
+    }
+    if merged.listed_on.is_none() {
+        merged.listed_on = meta.first_trade_date
+            .and_then(|timestamp: i64| DateTime::from_timestamp(timestamp, 0))
+            .map(|dt: DateTime<Utc>| timezone.date(dt));
+    }
+
+    merged
+}
 
-        self.fetch_historical_data(ticker, period1, period2).await
+fn asset_class(instrument_type: &str) -> Option<AssetClass> {
+    match instrument_type {
+        "EQUITY" => Some(AssetClass::Equity),
+        "ETF" => Some(AssetClass::Etf),
+        "INDEX" => Some(AssetClass::Index),
+        "CRYPTOCURRENCY" => Some(AssetClass::Crypto),
+        "CURRENCY" => Some(AssetClass::Fx),
+        _ => None,
     }
 }
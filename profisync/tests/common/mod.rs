@@ -0,0 +1,189 @@
+//! Local stand-ins for the services profisync talks to: a Yahoo chart API
+//! and a Stooq CSV download serving the recorded responses in
+//! `tests/fixtures`, and an in-process profiserve backed by memory storage.
+
+// Each test crate only uses some of the stand-ins
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use axum::extract::{Path, Query, RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::NaiveDate;
+use profiserve::models::{DateRangeQuery, HistoricalDataPoint, Stock};
+use profiserve::routes::create_router;
+use profiserve::state::AppState;
+use profiserve::storage::{MemoryStorage, Storage};
+
+/// Serves `router` on a free local port and returns its base URL.
+pub async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+
+    format!("http://{}", addr)
+}
+
+fn fixture(name: &str) -> Option<String> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name].iter().collect();
+    std::fs::read_to_string(path).ok()
+}
+
+/// State of the Yahoo stand-in: how many times `FLAKY` was asked for and
+/// the query of every chart request.
+#[derive(Clone, Default)]
+struct YahooState {
+    flaky_requests: Arc<AtomicUsize>,
+    queries: Arc<Mutex<Vec<String>>>,
+}
+
+/// Answers chart requests the way Yahoo does for each recorded ticker:
+/// `THROTTLED` is always rate limited, `FLAKY` is rate limited on its first
+/// request then served `AAPL`'s recording, `DELISTED` gets Yahoo's error
+/// payload with a 404 and the others get their recording, whatever range
+/// they ask for. Numbered copies such as `AAPL-2` share the recording of
+/// their ticker.
+async fn chart(State(state): State<YahooState>, Path(ticker): Path<String>, RawQuery(query): RawQuery) -> Response {
+    state.queries.lock().unwrap().push(query.unwrap_or_default());
+    let json = |status: StatusCode, body: String| {
+        (status, [("content-type", "application/json")], body).into_response()
+    };
+
+    match ticker.as_str() {
+        "THROTTLED" => (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests").into_response(),
+        "FLAKY" if state.flaky_requests.fetch_add(1, Ordering::SeqCst) == 0 => {
+            (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")], "Too Many Requests").into_response()
+        }
+        "FLAKY" => json(StatusCode::OK, fixture("yahoo/AAPL.json").unwrap()),
+        "DELISTED" => json(StatusCode::NOT_FOUND, fixture("yahoo/DELISTED.json").unwrap()),
+        _ => match fixture(&format!("yahoo/{}.json", ticker.split('-').next().unwrap())) {
+            Some(body) => json(StatusCode::OK, body),
+            None => json(StatusCode::NOT_FOUND, fixture("yahoo/DELISTED.json").unwrap()),
+        },
+    }
+}
+
+/// Yahoo stand-in, see [`chart`] for what it answers.
+pub struct Yahoo {
+    pub url: String,
+    queries: Arc<Mutex<Vec<String>>>,
+}
+
+impl Yahoo {
+    pub async fn spawn() -> Self {
+        let state: YahooState = YahooState::default();
+        let router = Router::new()
+            .route("/v8/finance/chart/:ticker", get(chart))
+            .with_state(state.clone());
+
+        Self { url: serve(router).await, queries: state.queries }
+    }
+
+    /// Queries of the chart requests received so far, in order.
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().unwrap().clone()
+    }
+}
+
+/// Starts the Yahoo stand-in and returns its base URL.
+pub async fn spawn_yahoo() -> String {
+    Yahoo::spawn().await.url
+}
+
+/// Stooq stand-in answering `/q/d/l/?s=<symbol>` with
+/// `tests/fixtures/stooq/<symbol>.csv`, or with `No data` like Stooq does
+/// for symbols it has nothing for. Requested symbols are recorded.
+pub struct Stooq {
+    pub url: String,
+    symbols: Arc<Mutex<Vec<String>>>,
+}
+
+async fn stooq_csv(
+    State(symbols): State<Arc<Mutex<Vec<String>>>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> String {
+    let symbol: String = query.get("s").cloned().unwrap_or_default();
+    symbols.lock().unwrap().push(symbol.clone());
+
+    fixture(&format!("stooq/{}.csv", symbol)).unwrap_or_else(|| "No data".to_string())
+}
+
+impl Stooq {
+    pub async fn spawn() -> Self {
+        let symbols: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let router = Router::new()
+            .route("/q/d/l/", get(stooq_csv))
+            .with_state(symbols.clone());
+
+        Self { url: serve(router).await, symbols }
+    }
+
+    /// Symbols requested so far, in order.
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols.lock().unwrap().clone()
+    }
+}
+
+pub struct Profiserve {
+    pub url: String,
+    pub storage: Arc<dyn Storage>,
+}
+
+impl Profiserve {
+    pub async fn spawn() -> Self {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let url = serve(create_router(AppState::new(storage.clone()))).await;
+
+        Self { url, storage }
+    }
+
+    pub async fn add_stock(&self, ticker: &str, stock_exchange: &str) {
+        let stock = Stock {
+            ticker: ticker.to_string(),
+            stock_exchange: stock_exchange.to_string(),
+            ..Stock::default()
+        };
+        self.storage.upsert_stock(&stock).await.unwrap();
+    }
+
+    /// Stores a flat bar for `ticker` on `date`.
+    pub async fn add_quote(&self, ticker: &str, date: NaiveDate) {
+        let data_point = HistoricalDataPoint {
+            date,
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume: 0,
+        };
+        self.storage.insert_data_point(ticker, &data_point).await.unwrap();
+    }
+
+    /// Close of the bar stored for `ticker` on `date`.
+    pub async fn close(&self, ticker: &str, date: NaiveDate) -> Option<f64> {
+        self.storage.get_data_point(ticker, date)
+            .await
+            .unwrap()
+            .map(|dp: HistoricalDataPoint| dp.close)
+    }
+
+    /// Dates of the bars stored for `ticker`, oldest first.
+    pub async fn dates(&self, ticker: &str) -> Vec<NaiveDate> {
+        self.storage.get_dates(ticker, &DateRangeQuery::default())
+            .await
+            .unwrap()
+            .unwrap_or_default()
+    }
+}
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+use chrono::NaiveDate;
+use proficalendar::{Calendars, TradingCalendar};
+use profisync::diff::{self, Gap, StockDiff};
+use profisync::models::HistoricalDataPoint;
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn bar(date: NaiveDate, close: f64) -> HistoricalDataPoint {
+    HistoricalDataPoint {
+        date,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 100,
+    }
+}
+
+#[test]
+fn splits_fetched_bars_into_created_and_changed() {
+    let fetched = vec![
+        bar(date(2024, 1, 2), 10.0),
+        bar(date(2024, 1, 3), 11.0),
+        bar(date(2024, 1, 4), 12.0),
+    ];
+    let stored = vec![
+        bar(date(2024, 1, 2), 10.0),
+        bar(date(2024, 1, 3), 10.5),
+    ];
+
+    let diff: StockDiff = diff::diff("AAPL", "yahoo", &fetched, &stored, &TradingCalendar::weekdays());
+
+    assert_eq!(diff.provider, Some("yahoo"));
+    assert_eq!(diff.created, vec![bar(date(2024, 1, 4), 12.0)]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].stored.close, 10.5);
+    assert_eq!(diff.changed[0].fetched.close, 11.0);
+    assert!(diff.gaps.is_empty());
+}
+
+#[test]
+fn gaps_skip_weekends_and_count_stored_bars() {
+    // Friday, then the Wednesday and Thursday of the following week
+    let fetched = vec![
+        bar(date(2024, 1, 5), 10.0),
+        bar(date(2024, 1, 10), 11.0),
+        bar(date(2024, 1, 11), 12.0),
+    ];
+    let stored = vec![bar(date(2024, 1, 8), 10.0)];
+
+    let diff: StockDiff = diff::diff("AAPL", "yahoo", &fetched, &stored, &TradingCalendar::weekdays());
+
+    assert_eq!(diff.gaps, vec![Gap { from: date(2024, 1, 9), to: date(2024, 1, 9), missing: 1 }]);
+}
+
+#[test]
+fn weekends_alone_are_not_gaps() {
+    let dates: BTreeSet<NaiveDate> = [date(2024, 1, 5), date(2024, 1, 8), date(2024, 1, 17)].into_iter().collect();
+
+    assert_eq!(diff::find_gaps(&dates, &TradingCalendar::weekdays()), vec![Gap { from: date(2024, 1, 9), to: date(2024, 1, 16), missing: 6 }]);
+}
+
+#[test]
+fn exchange_holidays_are_not_gaps() {
+    // Martin Luther King Jr. Day, the NYSE is closed on Monday the 15th
+    let dates: BTreeSet<NaiveDate> = [date(2024, 1, 12), date(2024, 1, 16)].into_iter().collect();
+    let calendars = Calendars::builtin();
+
+    assert!(diff::find_gaps(&dates, calendars.for_exchange("NYSE")).is_empty());
+    assert_eq!(diff::find_gaps(&dates, calendars.for_exchange("LSE")), vec![Gap { from: date(2024, 1, 15), to: date(2024, 1, 15), missing: 1 }]);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use common::{date, Profiserve, Stooq};
+use profisync::file_drop::{ColumnMapping, FileDropProvider};
+use profisync::market_data::{MarketDataProvider, ProviderChains};
+use profisync::models::HistoricalDataPoint;
+use profisync::rate_limit::RateLimiter;
+use profisync::reconciliation::{self, ReconciliationReport};
+use profisync::retry::RetryPolicy;
+use profisync::stooq::StooqClient;
+use profisync::sync_service::{SyncOptions, SyncService, SyncSummary};
+use profisync::yahoo_finance::YahooFinanceClient;
+
+fn yahoo(url: &str) -> Arc<dyn MarketDataProvider> {
+    Arc::new(YahooFinanceClient::new(url.to_string(), RetryPolicy::none(), RateLimiter::unlimited()))
+}
+
+fn stooq(stooq: &Stooq) -> Arc<dyn MarketDataProvider> {
+    Arc::new(StooqClient::new(stooq.url.clone(), RetryPolicy::none(), RateLimiter::unlimited()))
+}
+
+/// File drop reading from an empty directory of its own, returned along with it.
+fn file_drop(test: &str) -> (Arc<dyn MarketDataProvider>, PathBuf) {
+    let root: PathBuf = std::env::temp_dir().join(format!("profisync-fallback-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let drop: PathBuf = root.join("drop");
+    std::fs::create_dir_all(&drop).unwrap();
+
+    let provider = FileDropProvider::new(drop.clone(), root.join("archive"), ColumnMapping::default());
+    (Arc::new(provider), drop)
+}
+
+fn is_empty(directory: &Path) -> bool {
+    std::fs::read_dir(directory).unwrap().next().is_none()
+}
+
+fn sync_service(profiserve: &Profiserve, chain: Vec<Arc<dyn MarketDataProvider>>, options: SyncOptions) -> SyncService {
+    SyncService::new(profiserve.url.clone(), ProviderChains::new(chain), options, RetryPolicy::none())
+}
+
+fn bar(day: u32, close: f64) -> HistoricalDataPoint {
+    HistoricalDataPoint {
+        date: date(2024, 1, day),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 0,
+    }
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_provider_when_one_fails() {
+    let (yahoo_url, stooq_server) = (common::spawn_yahoo().await, Stooq::spawn().await);
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("THROTTLED", "NASDAQ").await;
+
+    let summary: SyncSummary = sync_service(&profiserve, vec![yahoo(&yahoo_url), stooq(&stooq_server)], SyncOptions::default())
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(summary.synchronized, 1);
+    assert_eq!(stooq_server.symbols(), vec!["throttled.us"]);
+    assert_eq!(profiserve.close("THROTTLED", date(2024, 1, 3)).await, Some(51.5));
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_provider_when_one_has_no_bars() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let (file, drop) = file_drop("no-bars");
+    std::fs::write(drop.join("EMPTY.csv"), "date,open,high,low,close\n2024-01-02,7,7,7,7\n").unwrap();
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("EMPTY", "NASDAQ").await;
+
+    sync_service(&profiserve, vec![yahoo(&yahoo_url), file], SyncOptions::default())
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(profiserve.close("EMPTY", date(2024, 1, 2)).await, Some(7.0));
+    assert!(is_empty(&drop));
+}
+
+#[tokio::test]
+async fn the_first_provider_with_bars_wins() {
+    let (yahoo_url, stooq_server) = (common::spawn_yahoo().await, Stooq::spawn().await);
+    let (file, drop) = file_drop("first-wins");
+    std::fs::write(drop.join("AAPL.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n").unwrap();
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    sync_service(&profiserve, vec![file, yahoo(&yahoo_url), stooq(&stooq_server)], SyncOptions::default())
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2)]);
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 2)).await, Some(1.0));
+    assert!(stooq_server.symbols().is_empty());
+}
+
+#[tokio::test]
+async fn a_stock_fails_only_when_every_provider_fails() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let (file, drop) = file_drop("all-fail");
+    std::fs::write(drop.join("THROTTLED.csv"), "date,open,high,low\n2024-01-02,1,1,1\n").unwrap();
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("THROTTLED", "NASDAQ").await;
+
+    let summary: SyncSummary = sync_service(&profiserve, vec![yahoo(&yahoo_url), file], SyncOptions::default())
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(summary.failed, 1);
+    assert!(profiserve.dates("THROTTLED").await.is_empty());
+    // The broken drop stays where it is to be fixed
+    assert!(!is_empty(&drop));
+}
+
+#[tokio::test]
+async fn reconciling_with_a_file_drop_leaves_its_files_alone() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let (file, drop) = file_drop("reconcile");
+    std::fs::write(drop.join("AAPL.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n2024-01-12,1,1,1,1\n").unwrap();
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    let options = SyncOptions {
+        reconcile_tolerance: Some(0.01),
+        ..SyncOptions::default()
+    };
+
+    sync_service(&profiserve, vec![yahoo(&yahoo_url), file], options)
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    // Yahoo's bars are stored while the compared file, never uploaded, is not archived
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 2)).await, Some(185.64));
+    assert!(profiserve.close("AAPL", date(2024, 1, 12)).await.is_none());
+    assert!(drop.join("AAPL.csv").exists());
+}
+
+#[test]
+fn closes_within_tolerance_are_not_mismatches() {
+    let primary = vec![bar(2, 100.0), bar(3, 100.0), bar(4, 100.0)];
+    let secondary = vec![bar(2, 100.5), bar(3, 102.0), bar(5, 50.0)];
+
+    let report: ReconciliationReport = reconciliation::reconcile("AAPL", ("yahoo", &primary), ("stooq", &secondary), 0.01);
+
+    assert_eq!((report.primary, report.secondary), ("yahoo", "stooq"));
+    assert_eq!(report.compared, 2);
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].date, date(2024, 1, 3));
+    assert_eq!(report.mismatches[0].secondary_close, 102.0);
+    assert!((report.mismatches[0].relative_difference - 0.02).abs() < 1e-9);
+}
+
+#[test]
+fn zero_closes_only_match_zero() {
+    let primary = vec![bar(2, 0.0), bar(3, 0.0)];
+    let secondary = vec![bar(2, 0.0), bar(3, 1.0)];
+
+    let report: ReconciliationReport = reconciliation::reconcile("AAPL", ("yahoo", &primary), ("stooq", &secondary), 0.01);
+
+    assert_eq!(report.compared, 2);
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].relative_difference, f64::INFINITY);
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::NaiveDate;
+use common::{date, Profiserve};
+use profisync::file_drop::{ColumnMapping, FileDropProvider};
+use profisync::market_data::{MarketDataProvider, ProviderChains};
+use profisync::models::{HistoricalDataPoint, Stock};
+use profisync::retry::RetryPolicy;
+use profisync::sync_service::{SyncOptions, SyncService};
+
+/// Empty drop and archive directories of their own for each test.
+fn directories(test: &str) -> (PathBuf, PathBuf) {
+    let root: PathBuf = std::env::temp_dir().join(format!("profisync-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    let (drop, archive): (PathBuf, PathBuf) = (root.join("drop"), root.join("archive"));
+    std::fs::create_dir_all(&drop).unwrap();
+    (drop, archive)
+}
+
+fn file_names(directory: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(directory)
+        .map(|entries| entries.map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn stock(ticker: &str) -> Stock {
+    Stock {
+        ticker: ticker.to_string(),
+        stock_exchange: "NASDAQ".to_string(),
+        ..Stock::default()
+    }
+}
+
+fn bar(date: NaiveDate, close: f64, volume: u64) -> HistoricalDataPoint {
+    HistoricalDataPoint {
+        date,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume,
+    }
+}
+
+async fn bars(provider: &FileDropProvider, ticker: &str) -> Vec<HistoricalDataPoint> {
+    provider.fetch_daily_bars(&stock(ticker), None, date(2024, 12, 31)).await.unwrap()
+}
+
+#[tokio::test]
+async fn reads_csv_with_any_header_case_and_optional_volume() {
+    let (drop, archive) = directories("csv");
+    std::fs::write(drop.join("ACME.csv"), "Date,Open,High,Low,Close,Volume\n2024-01-03,2,2,2,2,\n2024-01-02,1,1,1,1,1500.4\n").unwrap();
+    std::fs::write(drop.join("OTHER.csv"), "date,open,high,low,close\n2024-01-02,9,9,9,9\n").unwrap();
+    let provider = FileDropProvider::new(drop, archive, ColumnMapping::default());
+
+    assert_eq!(bars(&provider, "ACME").await, vec![
+        bar(date(2024, 1, 2), 1.0, 1500),
+        bar(date(2024, 1, 3), 2.0, 0),
+    ]);
+    assert_eq!(bars(&provider, "OTHER").await, vec![bar(date(2024, 1, 2), 9.0, 0)]);
+}
+
+#[tokio::test]
+async fn reads_json_with_numbers_given_as_strings() {
+    let (drop, archive) = directories("json");
+    let json = r#"[
+        {"date": "2024-01-02", "open": "1.5", "high": 1.5, "low": 1.5, "close": "1.5", "volume": "200"},
+        {"Date": "2024-01-03", "Open": 2, "High": 2, "Low": 2, "Close": 2}
+    ]"#;
+    std::fs::write(drop.join("ACME.json"), json).unwrap();
+    let provider = FileDropProvider::new(drop, archive, ColumnMapping::default());
+
+    assert_eq!(bars(&provider, "ACME").await, vec![
+        bar(date(2024, 1, 2), 1.5, 200),
+        bar(date(2024, 1, 3), 2.0, 0),
+    ]);
+}
+
+#[tokio::test]
+async fn maps_columns_and_date_formats() {
+    let (drop, archive) = directories("mapping");
+    std::fs::write(drop.join("ACME.csv"), "Day,O,H,L,Adj Close,Shares\n02/01/2024,1,3,1,2,10\n").unwrap();
+    let mut columns: ColumnMapping = ColumnMapping::parse("date=Day, open=O, high=H, low=L, close=Adj Close, volume=Shares").unwrap();
+    columns.date_format = "%d/%m/%Y".to_string();
+    let provider = FileDropProvider::new(drop, archive, columns);
+
+    let bars: Vec<HistoricalDataPoint> = bars(&provider, "ACME").await;
+
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].date, date(2024, 1, 2));
+    assert_eq!((bars[0].open, bars[0].high, bars[0].close, bars[0].volume), (1.0, 3.0, 2.0, 10));
+}
+
+#[test]
+fn rejects_unknown_fields_in_column_mappings() {
+    assert!(ColumnMapping::parse("date=Day,price=Close").is_err());
+    assert!(ColumnMapping::parse("close").is_err());
+}
+
+#[tokio::test]
+async fn reports_missing_columns_and_bad_values() {
+    let (drop, archive) = directories("errors");
+    std::fs::write(drop.join("NOCLOSE.csv"), "date,open,high,low\n2024-01-02,1,1,1\n").unwrap();
+    std::fs::write(drop.join("BADOPEN.csv"), "date,open,high,low,close\n2024-01-02,one,1,1,1\n").unwrap();
+    std::fs::write(drop.join("NOHIGH.json"), r#"[{"date":"2024-01-02","open":1,"high":1,"low":1,"close":1},{"date":"2024-01-03","open":1,"low":1,"close":1}]"#).unwrap();
+    let provider = FileDropProvider::new(drop, archive, ColumnMapping::default());
+
+    let missing = provider.fetch_daily_bars(&stock("NOCLOSE"), None, date(2024, 12, 31)).await.unwrap_err();
+    assert!(missing.to_string().contains("missing column close"), "{}", missing);
+    let invalid = provider.fetch_daily_bars(&stock("BADOPEN"), None, date(2024, 12, 31)).await.unwrap_err();
+    assert!(invalid.to_string().contains("invalid open \"one\" at 2"), "{}", invalid);
+    // Bars of JSON files are counted from one, like the lines of CSV files
+    let missing_json = provider.fetch_daily_bars(&stock("NOHIGH"), None, date(2024, 12, 31)).await.unwrap_err();
+    assert!(missing_json.to_string().contains("missing high in bar 2"), "{}", missing_json);
+}
+
+#[tokio::test]
+async fn later_files_win_for_the_same_date() {
+    let (drop, archive) = directories("later-wins");
+    std::fs::write(drop.join("ACME.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n2024-01-03,1,1,1,1\n").unwrap();
+    std::fs::write(drop.join("ACME_2024-02.csv"), "date,open,high,low,close\n2024-01-03,3,3,3,3\n").unwrap();
+    // Other tickers whose names start the same way are not mixed in
+    std::fs::write(drop.join("ACMEX.csv"), "date,open,high,low,close\n2024-01-03,9,9,9,9\n").unwrap();
+    std::fs::write(drop.join("ACME_B.csv"), "date,open,high,low,close\n2024-01-03,8,8,8,8\n").unwrap();
+    std::fs::write(drop.join("acme_b_2024-02.csv"), "date,open,high,low,close\n2024-01-03,7,7,7,7\n").unwrap();
+    let provider = FileDropProvider::new(drop, archive, ColumnMapping::default());
+
+    assert_eq!(bars(&provider, "ACME").await, vec![
+        bar(date(2024, 1, 2), 1.0, 0),
+        bar(date(2024, 1, 3), 3.0, 0),
+    ]);
+}
+
+#[tokio::test]
+async fn archives_only_the_files_read_for_the_stock() {
+    let (drop, archive) = directories("archive");
+    std::fs::write(drop.join("ACME.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n").unwrap();
+    std::fs::write(drop.join("ACME_2.json"), "[]").unwrap();
+    std::fs::write(drop.join("ACME_B.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n").unwrap();
+    std::fs::write(drop.join("OTHER.csv"), "date,open,high,low,close\n2024-01-02,1,1,1,1\n").unwrap();
+    let provider = FileDropProvider::new(drop.clone(), archive.clone(), ColumnMapping::default());
+
+    // Nothing is archived for a stock that was not fetched
+    provider.after_sync(&stock("OTHER")).await.unwrap();
+    assert!(file_names(&archive).is_empty());
+
+    bars(&provider, "ACME").await;
+    provider.after_sync(&stock("ACME")).await.unwrap();
+
+    assert_eq!(file_names(&drop), vec!["ACME_B.csv", "OTHER.csv"]);
+    let archived: Vec<String> = file_names(&archive);
+    assert_eq!(archived.len(), 2);
+    assert!(archived[0].ends_with("_ACME.csv"), "{:?}", archived);
+    assert!(archived[1].ends_with("_ACME_2.json"), "{:?}", archived);
+}
+
+#[tokio::test]
+async fn every_dropped_row_is_uploaded_before_archiving() {
+    let (drop, archive) = directories("sync");
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("ACME", "NASDAQ").await;
+    profiserve.add_quote("ACME", date(2024, 1, 5)).await;
+    // Rows both before and after the latest stored bar
+    std::fs::write(
+        drop.join("ACME.csv"),
+        "date,open,high,low,close\n2024-01-03,2,2,2,2\n2024-01-04,2,2,2,2\n2024-01-08,2,2,2,2\n",
+    )
+    .unwrap();
+    let provider: Arc<dyn MarketDataProvider> = Arc::new(FileDropProvider::new(drop.clone(), archive.clone(), ColumnMapping::default()));
+    let service = SyncService::new(profiserve.url.clone(), ProviderChains::new(vec![provider]), SyncOptions::default(), RetryPolicy::none());
+
+    service.sync_all_stocks().await.unwrap();
+
+    assert_eq!(
+        profiserve.dates("ACME").await,
+        vec![date(2024, 1, 3), date(2024, 1, 4), date(2024, 1, 5), date(2024, 1, 8)]
+    );
+    assert!(file_names(&drop).is_empty());
+    assert_eq!(file_names(&archive).len(), 1);
+}
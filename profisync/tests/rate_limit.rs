@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use profisync::rate_limit::{RateLimit, RateLimiter};
+
+#[test]
+fn parses_rate_limit_specs() {
+    let limit = RateLimit::parse("2/s, 100/m, burst=5").unwrap();
+    assert_eq!(limit.per_second, Some(2.0));
+    assert_eq!(limit.per_minute, Some(100.0));
+    assert_eq!(limit.burst, 5);
+
+    assert!(RateLimit::parse("none").unwrap().is_unlimited());
+    assert!(RateLimit::parse("").unwrap().is_unlimited());
+    assert!(RateLimit::parse("2/h").is_err());
+    assert!(RateLimit::parse("0/s").is_err());
+}
+
+#[tokio::test]
+async fn spaces_requests_after_the_burst() {
+    let limiter = RateLimiter::new(RateLimit::parse("20/s,burst=2").unwrap());
+
+    let start = Instant::now();
+    for _ in 0..6 {
+        limiter.acquire().await;
+    }
+
+    // Two requests go through at once, the other four wait 50 ms each
+    assert!(start.elapsed() >= Duration::from_millis(190), "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn limiter_is_shared_by_concurrent_callers() {
+    let limiter = Arc::new(RateLimiter::new(RateLimit::parse("20/s").unwrap()));
+
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..5)
+        .map(|_| {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(190), "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn unlimited_limiter_never_waits() {
+    let limiter = RateLimiter::unlimited();
+
+    let start = Instant::now();
+    for _ in 0..1_000 {
+        limiter.acquire().await;
+    }
+
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{date, Stooq};
+use profisync::market_data::MarketDataProvider;
+use profisync::models::{HistoricalDataPoint, Stock};
+use profisync::rate_limit::RateLimiter;
+use profisync::retry::RetryPolicy;
+use profisync::stooq::StooqClient;
+
+fn stooq_client(stooq: &Stooq) -> StooqClient {
+    StooqClient::new(stooq.url.clone(), RetryPolicy::none(), RateLimiter::unlimited())
+}
+
+fn stock(ticker: &str, stock_exchange: &str) -> Stock {
+    Stock {
+        ticker: ticker.to_string(),
+        stock_exchange: stock_exchange.to_string(),
+        ..Stock::default()
+    }
+}
+
+#[tokio::test]
+async fn us_tickers_are_requested_with_the_us_suffix() {
+    let stooq = Stooq::spawn().await;
+    let client = stooq_client(&stooq);
+
+    let bars: Vec<HistoricalDataPoint> = client.fetch_daily_bars(&stock("AAPL", "NASDAQ"), None, date(2024, 1, 3)).await.unwrap();
+
+    assert_eq!(stooq.symbols(), vec!["aapl.us"]);
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0], HistoricalDataPoint {
+        date: date(2024, 1, 2),
+        open: 187.15,
+        high: 188.44,
+        low: 183.885,
+        close: 185.64,
+        volume: 82488674,
+    });
+    // Stooq's volumes may have decimals
+    assert_eq!(bars[1].volume, 58414461);
+}
+
+#[tokio::test]
+async fn suffixed_tickers_and_indices_are_passed_through() {
+    let stooq = Stooq::spawn().await;
+    let client = stooq_client(&stooq);
+
+    client.fetch_daily_bars(&stock("VOD.UK", "LSE"), None, date(2024, 1, 3)).await.unwrap();
+    let bars: Vec<HistoricalDataPoint> = client.fetch_daily_bars(&stock("^SPX", "INDEX"), None, date(2024, 1, 3)).await.unwrap();
+    client.fetch_daily_bars(&stock("PKN", "WSE"), None, date(2024, 1, 3)).await.unwrap();
+
+    assert_eq!(stooq.symbols(), vec!["vod.uk", "^spx", "pkn"]);
+    // Indices have no volume column
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].close, 4742.83);
+    assert_eq!(bars[0].volume, 0);
+}
+
+#[tokio::test]
+async fn no_data_means_no_bars() {
+    let stooq = Stooq::spawn().await;
+    let client = stooq_client(&stooq);
+
+    let bars: Vec<HistoricalDataPoint> = client.fetch_daily_bars(&stock("MSFT", "NASDAQ"), Some(date(2024, 1, 2)), date(2024, 1, 3)).await.unwrap();
+
+    assert_eq!(stooq.symbols(), vec!["msft.us"]);
+    assert!(bars.is_empty());
+}
+
+#[tokio::test]
+async fn malformed_rows_are_reported_with_their_line() {
+    let stooq = Stooq::spawn().await;
+    let client = stooq_client(&stooq);
+
+    let error = client.fetch_daily_bars(&stock("BROKEN", "NYSE"), None, date(2024, 1, 3)).await.unwrap_err();
+
+    assert!(error.to_string().starts_with("Invalid Stooq CSV on line 3"), "{}", error);
+}
@@ -0,0 +1,562 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{Days, NaiveDate, Utc};
+use common::{date, Profiserve, Stooq, Yahoo};
+use profisync::market_data::{MarketDataProvider, ProviderChains};
+use profisync::models::{Dividend, HistoryGapList, Split, Stock};
+use profisync::profiserve_client::ProfiserveClient;
+use profisync::rate_limit::RateLimiter;
+use profisync::retry::RetryPolicy;
+use profisync::stooq::StooqClient;
+use profisync::sync_service::{SyncOptions, SyncService, SyncSummary};
+use profisync::yahoo_finance::YahooFinanceClient;
+
+fn sync_service(profiserve: &Profiserve, yahoo_url: &str, retry_policy: RetryPolicy) -> SyncService {
+    sync_service_with(profiserve, yahoo_url, retry_policy, SyncOptions::default())
+}
+
+fn sync_service_with(profiserve: &Profiserve, yahoo_url: &str, retry_policy: RetryPolicy, options: SyncOptions) -> SyncService {
+    let yahoo: Arc<dyn MarketDataProvider> = Arc::new(YahooFinanceClient::new(yahoo_url.to_string(), retry_policy.clone(), RateLimiter::unlimited()));
+
+    SyncService::new(profiserve.url.clone(), ProviderChains::new(vec![yahoo]), options, retry_policy)
+}
+
+/// Retries quickly enough to keep the suite fast.
+fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    }
+}
+
+fn stock(ticker: &str) -> Stock {
+    Stock {
+        ticker: ticker.to_string(),
+        stock_exchange: "NASDAQ".to_string(),
+        ..Stock::default()
+    }
+}
+
+#[tokio::test]
+async fn syncs_full_history_and_skips_null_quotes() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    let dates: Vec<NaiveDate> = profiserve.dates("AAPL").await;
+    assert_eq!(dates, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+}
+
+#[tokio::test]
+async fn syncing_again_uploads_nothing_new() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    let service = sync_service(&profiserve, &yahoo_url, RetryPolicy::none());
+    service.sync_all_stocks().await.unwrap();
+    service.sync_all_stocks().await.unwrap();
+
+    assert_eq!(profiserve.dates("AAPL").await.len(), 3);
+}
+
+#[tokio::test]
+async fn empty_results_leave_history_empty() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("EMPTY", "NASDAQ").await;
+    profiserve.add_stock("NOBARS", "NASDAQ").await;
+
+    sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    assert!(profiserve.dates("EMPTY").await.is_empty());
+    assert!(profiserve.dates("NOBARS").await.is_empty());
+}
+
+#[tokio::test]
+async fn failing_tickers_do_not_stop_the_others() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("DELISTED", "NASDAQ").await;
+    profiserve.add_stock("THROTTLED", "NASDAQ").await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    let summary: SyncSummary = sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 0, failed: 2 });
+    assert!(profiserve.dates("DELISTED").await.is_empty());
+    assert!(profiserve.dates("THROTTLED").await.is_empty());
+    assert_eq!(profiserve.dates("AAPL").await.len(), 3);
+}
+
+#[tokio::test]
+async fn throttled_requests_are_retried() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("FLAKY", "NASDAQ").await;
+    profiserve.add_stock("THROTTLED", "NASDAQ").await;
+
+    sync_service(&profiserve, &yahoo_url, fast_retries()).sync_all_stocks().await.unwrap();
+
+    assert_eq!(profiserve.dates("FLAKY").await.len(), 3);
+    assert!(profiserve.dates("THROTTLED").await.is_empty());
+}
+
+#[tokio::test]
+async fn yahoo_errors_are_reported() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+    let today: NaiveDate = chrono::Utc::now().date_naive();
+
+    let delisted = yahoo.fetch_daily_bars(&stock("DELISTED"), None, today).await.unwrap_err();
+    assert!(delisted.to_string().contains("No data found, symbol may be delisted"), "{}", delisted);
+
+    let throttled = yahoo.fetch_daily_bars(&stock("THROTTLED"), None, today).await.unwrap_err();
+    assert!(throttled.to_string().contains("429"), "{}", throttled);
+}
+
+#[tokio::test]
+async fn chart_meta_fills_missing_metadata() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+
+    let mut aapl: Stock = stock("AAPL");
+    aapl.name = Some("Apple".to_string());
+
+    let merged: Stock = yahoo.fetch_metadata(&aapl).await.unwrap().unwrap();
+    assert_eq!(merged.name.as_deref(), Some("Apple"));
+    assert_eq!(merged.currency.as_deref(), Some("USD"));
+    assert_eq!(merged.listed_on, Some(date(1980, 12, 12)));
+}
+
+#[tokio::test]
+async fn minor_currency_units_keep_their_case() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("VOD.L", "LSE").await;
+    let options = SyncOptions {
+        enrich_metadata: true,
+        ..SyncOptions::default()
+    };
+
+    sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), options).sync_all_stocks().await.unwrap();
+
+    let stock: profiserve::models::Stock = profiserve.storage.get_stock("VOD.L").await.unwrap().unwrap();
+    assert_eq!(stock.currency.as_deref(), Some("GBp"));
+}
+
+#[tokio::test]
+async fn listing_dates_are_in_exchange_time() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+    let toyota = Stock {
+        stock_exchange: "TSE".to_string(),
+        ..stock("7203.T")
+    };
+
+    // First traded at 08:00 on January 4 in Tokyo, still January 3 in UTC
+    let merged: Stock = yahoo.fetch_metadata(&toyota).await.unwrap().unwrap();
+    assert_eq!(merged.listed_on, Some(date(2000, 1, 4)));
+}
+
+async fn bar_dates(yahoo: &YahooFinanceClient, ticker: &str, exchange: &str) -> Vec<NaiveDate> {
+    let listed = Stock {
+        stock_exchange: exchange.to_string(),
+        ..stock(ticker)
+    };
+    let today: NaiveDate = chrono::Utc::now().date_naive();
+
+    yahoo.fetch_daily_bars(&listed, None, today).await.unwrap()
+        .iter()
+        .map(|dp| dp.date)
+        .collect()
+}
+
+#[tokio::test]
+async fn tokyo_bars_are_dated_in_exchange_time() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+
+    // Stamped at midnight in Tokyo, 15:00 the day before in UTC
+    let expected: Vec<NaiveDate> = vec![date(2024, 1, 4), date(2024, 1, 5), date(2024, 1, 9)];
+    assert_eq!(bar_dates(&yahoo, "7203.T", "TSE").await, expected);
+    // The chart names its time zone, the stock's exchange does not matter
+    assert_eq!(bar_dates(&yahoo, "7203.T", "OTC").await, expected);
+}
+
+#[tokio::test]
+async fn sydney_bars_fall_back_to_exchange_time_zones() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+
+    // The chart only has a UTC offset, the ASX calendar's time zone comes first
+    let expected: Vec<NaiveDate> = vec![date(2024, 1, 2), date(2024, 1, 3), date(2024, 1, 4)];
+    assert_eq!(bar_dates(&yahoo, "BHP.AX", "ASX").await, expected);
+    assert_eq!(bar_dates(&yahoo, "BHP.AX", "CHIXAU").await, expected);
+}
+
+#[tokio::test]
+async fn new_york_bars_keep_their_session_date() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let yahoo = YahooFinanceClient::new(yahoo_url, RetryPolicy::none(), RateLimiter::unlimited());
+
+    let expected: Vec<NaiveDate> = vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)];
+    assert_eq!(bar_dates(&yahoo, "AAPL", "NASDAQ").await, expected);
+}
+
+#[tokio::test]
+async fn stocks_are_synchronized_concurrently() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    let tickers: Vec<String> = (0..6).map(|i: usize| format!("AAPL-{}", i)).collect();
+    for ticker in &tickers {
+        profiserve.add_stock(ticker, "NASDAQ").await;
+    }
+    profiserve.add_stock("DELISTED", "NASDAQ").await;
+
+    sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    for ticker in &tickers {
+        assert_eq!(profiserve.dates(ticker).await.len(), 3, "{}", ticker);
+    }
+    assert!(profiserve.dates("DELISTED").await.is_empty());
+}
+
+#[tokio::test]
+async fn backfill_fills_dates_before_the_latest_quote() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    profiserve.add_quote("AAPL", date(2024, 1, 5)).await;
+
+    let service = sync_service(&profiserve, &yahoo_url, RetryPolicy::none());
+
+    // A regular pass only looks after the latest quote
+    service.sync_all_stocks().await.unwrap();
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 5)]);
+
+    let summary: SyncSummary = service.backfill("aapl", date(2024, 1, 1), Some(date(2024, 1, 4))).await.unwrap();
+
+    assert_eq!(summary.synchronized, 1);
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+}
+
+#[tokio::test]
+async fn backfill_rejects_unknown_tickers_and_reversed_ranges() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    let service = sync_service(&profiserve, &yahoo_url, RetryPolicy::none());
+
+    assert!(service.backfill("MSFT", date(2024, 1, 1), Some(date(2024, 1, 4))).await.is_err());
+    assert!(service.backfill("AAPL", date(2024, 1, 4), Some(date(2024, 1, 1))).await.is_err());
+    assert!(profiserve.dates("AAPL").await.is_empty());
+}
+
+#[tokio::test]
+async fn backfill_ends_at_the_latest_session_by_default() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+
+    let service = sync_service(&profiserve, &yahoo_url, RetryPolicy::none());
+
+    // Sessions that have not closed yet are out of range
+    let next_week: NaiveDate = Utc::now().date_naive() + Days::new(7);
+    assert!(service.backfill("AAPL", next_week, None).await.is_err());
+
+    service.backfill("AAPL", date(2024, 1, 1), None).await.unwrap();
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+}
+
+#[tokio::test]
+async fn dry_run_writes_nothing() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    profiserve.add_stock("EMPTY", "NASDAQ").await;
+    profiserve.add_quote("AAPL", date(2024, 1, 2)).await;
+
+    let options = SyncOptions {
+        dry_run: true,
+        ..SyncOptions::default()
+    };
+    let service = sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), options);
+
+    let summary: SyncSummary = service.backfill("AAPL", date(2024, 1, 1), Some(date(2024, 1, 5))).await.unwrap();
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 0, failed: 0 });
+
+    let summary: SyncSummary = service.sync_all_stocks().await.unwrap();
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 1, failed: 0 });
+
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2)]);
+    assert!(profiserve.dates("EMPTY").await.is_empty());
+}
+
+#[tokio::test]
+async fn lookback_revises_changed_bars() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    profiserve.add_quote("AAPL", date(2024, 1, 2)).await;
+    profiserve.add_quote("AAPL", date(2024, 1, 5)).await;
+
+    // Without a look-back the stale bars are left alone
+    sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 5)).await, Some(1.0));
+
+    let options = SyncOptions {
+        lookback_days: 3,
+        ..SyncOptions::default()
+    };
+    let summary: SyncSummary = sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), options)
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(summary.synchronized, 1);
+    // The window covers January 3 to 5, so January 4 is created and 5 revised
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 5)).await, Some(181.18));
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 2)).await, Some(1.0));
+}
+
+#[tokio::test]
+async fn profiserve_reports_missing_trading_days() {
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    for day in [2, 4, 9, 10, 12, 16] {
+        profiserve.add_quote("AAPL", date(2024, 1, day)).await;
+    }
+
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+    let gap_list: HistoryGapList = client.get_gaps("AAPL").await.unwrap().unwrap();
+
+    // Friday the 5th and Monday the 8th make up a single gap over the
+    // weekend, NASDAQ is closed on Monday the 15th
+    let gaps: Vec<(NaiveDate, NaiveDate, usize)> = gap_list.gaps.iter()
+        .map(|gap| (gap.from, gap.to, gap.missing))
+        .collect();
+    assert_eq!(gaps, vec![
+        (date(2024, 1, 3), date(2024, 1, 3), 1),
+        (date(2024, 1, 5), date(2024, 1, 8), 2),
+        (date(2024, 1, 11), date(2024, 1, 11), 1),
+    ]);
+    assert_eq!((gap_list.expected, gap_list.stored), (10, 6));
+
+    assert!(client.get_gaps("MSFT").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn fill_gaps_fetches_only_missing_ranges() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    profiserve.add_quote("AAPL", date(2024, 1, 2)).await;
+    profiserve.add_quote("AAPL", date(2024, 1, 5)).await;
+
+    let options = SyncOptions {
+        fill_gaps: true,
+        ..SyncOptions::default()
+    };
+    let summary: SyncSummary = sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), options)
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(summary.synchronized, 1);
+    // Yahoo has no bar for January 3, the stored bars around the gap are kept
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 4)).await, Some(181.91));
+    assert_eq!(profiserve.close("AAPL", date(2024, 1, 5)).await, Some(1.0));
+}
+
+#[tokio::test]
+async fn gaps_no_provider_has_are_not_fetched_again() {
+    let stooq = Stooq::spawn().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("GAPPY", "NASDAQ").await;
+    profiserve.add_quote("GAPPY", date(2024, 1, 2)).await;
+    profiserve.add_quote("GAPPY", date(2024, 1, 5)).await;
+
+    let provider: Arc<dyn MarketDataProvider> = Arc::new(StooqClient::new(stooq.url.clone(), RetryPolicy::none(), RateLimiter::unlimited()));
+    let options = SyncOptions {
+        fill_gaps: true,
+        ..SyncOptions::default()
+    };
+    let service = SyncService::new(profiserve.url.clone(), ProviderChains::new(vec![provider]), options, RetryPolicy::none());
+
+    // New bars then the gap of January 3 and 4, then only new bars
+    service.sync_all_stocks().await.unwrap();
+    assert_eq!(stooq.symbols().len(), 2);
+    service.sync_all_stocks().await.unwrap();
+    assert_eq!(stooq.symbols().len(), 3);
+}
+
+#[tokio::test]
+async fn empty_gaps_are_fetched_again_once_the_retry_delay_passed() {
+    let stooq = Stooq::spawn().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("GAPPY", "NASDAQ").await;
+    profiserve.add_quote("GAPPY", date(2024, 1, 2)).await;
+    profiserve.add_quote("GAPPY", date(2024, 1, 5)).await;
+
+    let provider: Arc<dyn MarketDataProvider> = Arc::new(StooqClient::new(stooq.url.clone(), RetryPolicy::none(), RateLimiter::unlimited()));
+    let options = SyncOptions {
+        fill_gaps: true,
+        empty_gap_retry: Duration::ZERO,
+        ..SyncOptions::default()
+    };
+    let service = SyncService::new(profiserve.url.clone(), ProviderChains::new(vec![provider]), options, RetryPolicy::none());
+
+    service.sync_all_stocks().await.unwrap();
+    service.sync_all_stocks().await.unwrap();
+
+    // New bars and the gap on both passes
+    assert_eq!(stooq.symbols().len(), 4);
+}
+
+#[tokio::test]
+async fn look_back_longer_than_the_calendar_fetches_everything() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("AAPL", "NASDAQ").await;
+    profiserve.add_quote("AAPL", date(2024, 1, 5)).await;
+
+    let options = SyncOptions {
+        lookback_days: u32::MAX,
+        ..SyncOptions::default()
+    };
+    sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), options)
+        .sync_all_stocks()
+        .await
+        .unwrap();
+
+    assert_eq!(profiserve.dates("AAPL").await, vec![date(2024, 1, 2), date(2024, 1, 4), date(2024, 1, 5)]);
+}
+
+#[tokio::test]
+async fn profiserve_stores_corporate_actions() {
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("NVDA", "NASDAQ").await;
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+
+    let split = Split { date: date(2024, 6, 10), numerator: 10.0, denominator: 1.0 };
+    client.create_split("NVDA", &split).await.unwrap();
+    for day in [5, 11] {
+        client.create_dividend("NVDA", &Dividend { date: date(2024, 6, day), amount: 0.04 }).await.unwrap();
+    }
+    client.update_dividend("NVDA", &Dividend { date: date(2024, 6, 11), amount: 0.01 }).await.unwrap();
+
+    assert_eq!(client.get_splits("NVDA", None, date(2024, 12, 31)).await.unwrap(), vec![split]);
+    assert!(client.get_splits("NVDA", Some(date(2024, 6, 11)), date(2024, 12, 31)).await.unwrap().is_empty());
+    assert_eq!(
+        client.get_dividends("NVDA", Some(date(2024, 6, 6)), date(2024, 6, 30)).await.unwrap(),
+        vec![Dividend { date: date(2024, 6, 11), amount: 0.01 }]
+    );
+
+    let invalid = client.create_split("NVDA", &Split { date: date(2024, 6, 12), numerator: 0.0, denominator: 1.0 }).await.unwrap_err();
+    assert!(invalid.to_string().contains("422"), "{}", invalid);
+    let missing = client.update_split("NVDA", &Split { date: date(2024, 6, 12), numerator: 2.0, denominator: 1.0 }).await.unwrap_err();
+    assert!(missing.to_string().contains("404"), "{}", missing);
+    let unknown = client.get_dividends("MSFT", None, date(2024, 12, 31)).await.unwrap_err();
+    assert!(unknown.to_string().contains("404"), "{}", unknown);
+
+    // Corporate actions keep the stock from being deleted without cascade, then go with it
+    assert!(profiserve.storage.delete_stock("NVDA", false).await.is_err());
+    profiserve.storage.delete_stock("NVDA", true).await.unwrap();
+    profiserve.add_stock("NVDA", "NASDAQ").await;
+    assert!(client.get_splits("NVDA", None, date(2024, 12, 31)).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn corporate_actions_are_synchronized_with_bars() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("NVDA", "NASDAQ").await;
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+    // Stored before Yahoo corrected the amount
+    client.create_dividend("NVDA", &Dividend { date: date(2024, 6, 11), amount: 0.1 }).await.unwrap();
+
+    let dry_run = SyncOptions {
+        dry_run: true,
+        ..SyncOptions::default()
+    };
+    let summary: SyncSummary = sync_service_with(&profiserve, &yahoo_url, RetryPolicy::none(), dry_run)
+        .sync_all_stocks()
+        .await
+        .unwrap();
+    assert_eq!(summary.synchronized, 1);
+    assert!(client.get_splits("NVDA", None, date(2024, 12, 31)).await.unwrap().is_empty());
+
+    let summary: SyncSummary = sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 0, failed: 0 });
+
+    assert_eq!(profiserve.dates("NVDA").await, vec![date(2024, 6, 7), date(2024, 6, 10), date(2024, 6, 11)]);
+    assert_eq!(
+        client.get_splits("NVDA", None, date(2024, 12, 31)).await.unwrap(),
+        vec![Split { date: date(2024, 6, 10), numerator: 10.0, denominator: 1.0 }]
+    );
+    assert_eq!(
+        client.get_dividends("NVDA", None, date(2024, 12, 31)).await.unwrap(),
+        vec![Dividend { date: date(2024, 6, 11), amount: 0.01 }]
+    );
+}
+
+#[tokio::test]
+async fn rejected_corporate_actions_do_not_fail_the_stock() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    // Yahoo reports a split with a zero ratio, which profiserve refuses
+    profiserve.add_stock("BADSPLIT", "NASDAQ").await;
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+
+    let summary: SyncSummary = sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 0, failed: 0 });
+    assert_eq!(profiserve.dates("BADSPLIT").await, vec![date(2024, 6, 7), date(2024, 6, 10)]);
+    assert!(client.get_splits("BADSPLIT", None, date(2024, 12, 31)).await.unwrap().is_empty());
+    assert_eq!(
+        client.get_dividends("BADSPLIT", None, date(2024, 12, 31)).await.unwrap(),
+        vec![Dividend { date: date(2024, 6, 10), amount: 0.05 }]
+    );
+}
+
+#[tokio::test]
+async fn bars_and_corporate_actions_come_from_one_chart_request() {
+    let yahoo = Yahoo::spawn().await;
+    let profiserve = Profiserve::spawn().await;
+    profiserve.add_stock("NVDA", "NASDAQ").await;
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+
+    sync_service(&profiserve, &yahoo.url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    let queries: Vec<String> = yahoo.queries();
+    assert_eq!(queries.len(), 1, "{:?}", queries);
+    assert!(queries[0].contains("events=div,splits"), "{}", queries[0]);
+    assert_eq!(client.get_splits("NVDA", None, date(2024, 12, 31)).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn corporate_actions_are_recorded_without_new_bars() {
+    let yahoo_url = common::spawn_yahoo().await;
+    let profiserve = Profiserve::spawn().await;
+    // Halted on its ex-dividend day, Yahoo has the dividend but no bar
+    profiserve.add_stock("HALTED", "NASDAQ").await;
+    let client = ProfiserveClient::new(profiserve.url.clone(), RetryPolicy::none());
+
+    let summary: SyncSummary = sync_service(&profiserve, &yahoo_url, RetryPolicy::none()).sync_all_stocks().await.unwrap();
+
+    assert_eq!(summary, SyncSummary { synchronized: 1, unchanged: 0, failed: 0 });
+    assert!(profiserve.dates("HALTED").await.is_empty());
+    assert_eq!(
+        client.get_dividends("HALTED", None, date(2024, 12, 31)).await.unwrap(),
+        vec![Dividend { date: date(2024, 6, 11), amount: 0.25 }]
+    );
+}